@@ -0,0 +1,27 @@
+import { Controller } from "@hotwired/stimulus";
+
+const FRAGMENT_HEADER = "Plethora-Fragment";
+const TITLE_HEADER = "Plethora-Title";
+
+export class FragmentController extends Controller<HTMLElement> {
+  static targets = ["content"];
+  static values = { name: String, url: String };
+
+  declare contentTarget: HTMLElement;
+  declare nameValue: string;
+  declare urlValue: string;
+
+  // Re-renders the named fragment of the page without its layout, and
+  // replaces the content target with it.
+  async refresh() {
+    const res = await fetch(this.urlValue || location.href, {
+      headers: { [FRAGMENT_HEADER]: this.nameValue },
+    });
+    if (!res.ok) throw new Error(`failed to fetch fragment ${this.nameValue}`);
+
+    this.contentTarget.innerHTML = await res.text();
+
+    const title = res.headers.get(TITLE_HEADER);
+    if (title) document.title = decodeURIComponent(title);
+  }
+}
@@ -3,8 +3,10 @@ declare global {
 }
 
 import { Application } from "@hotwired/stimulus";
+import { FragmentController } from "../controllers/fragment";
 import { ToastsController } from "../controllers/toasts";
 
 window.Stimulus ??= Application.start();
 
+Stimulus.register("fragment", FragmentController);
 Stimulus.register("toasts", ToastsController);
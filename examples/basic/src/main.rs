@@ -1,19 +1,27 @@
 use plethora::{
     axum::{
-        extract::FromRequestParts, http::request::Parts, middleware::from_fn_with_state,
-        routing::get, Router,
+        extract::FromRequestParts,
+        http::request::Parts,
+        middleware::from_fn_with_state,
+        response::Redirect,
+        routing::{get, post},
+        Router,
     },
     db::{Db, Id},
     error::Result,
+    fingerprint::Fingerprints,
+    languages::Languages,
     reload::Reloader,
     scripts::Scripts,
     serve::{
-        current, public_router, Application, CurrentHooks, CurrentState, Renderer, ServeResult,
+        csrf, current, public_router, switch_theme, Application, CurrentHooks, CurrentState, Re,
+        Renderer, ServeResult,
     },
+    sessions::{Sessions, StoredSession},
     styles::Styles,
     themes::{props, Themes},
     tower::ServiceBuilder,
-    tower_cookies::CookieManagerLayer,
+    tower_cookies::{CookieManagerLayer, Cookies},
 };
 use serde::Serialize;
 use std::convert::Infallible;
@@ -24,31 +32,49 @@ async fn main() -> Result<()> {
     plethora::scratch::init().await?;
 
     let db = Db::new().await?;
+    db.migrate().await?;
+
+    let sessions = Sessions::new(db.clone()).await?;
     let styles = Styles::new().await?;
     let themes = Themes::new(styles.clone()).await?;
     let scripts = Scripts::new().await?;
+    let languages = Languages::new().await?;
+    let fingerprints = Fingerprints::new().await?;
     let reloader = Reloader::new()
         .reload(themes.clone())
         .reload(scripts.clone())
+        .reload(languages.clone())
+        .reload(fingerprints)
         .build();
 
     let app = App {
         db,
+        sessions,
         styles,
         themes,
         scripts,
+        languages,
         reloader,
     };
 
     let cookies = CookieManagerLayer::new();
     let current = from_fn_with_state(app.clone(), current::<Current, App>);
+    let csrf = from_fn_with_state(app.clone(), csrf::<Render, App>);
 
     let app_router = Router::new()
         .route("/", get(index))
-        .layer(ServiceBuilder::new().layer(cookies).layer(current))
+        .route("/login", post(login))
+        .route("/logout", post(logout))
+        .route("/__theme__", post(switch_theme))
+        .layer(
+            ServiceBuilder::new()
+                .layer(cookies)
+                .layer(current)
+                .layer(csrf),
+        )
         .with_state(app.clone());
 
-    let router = Router::new().merge(public_router(app)).merge(app_router);
+    let router = Router::new().merge(public_router(app)?).merge(app_router);
 
     plethora::serve(router).await
 }
@@ -57,12 +83,27 @@ async fn index(re: Render) -> ServeResult {
     re.render("index", props!({}))
 }
 
+async fn login(re: Render, cookies: Cookies) -> ServeResult<Redirect> {
+    // A real application would check credentials against its users here.
+    let user_id = Id::new_v4();
+    re.app.sessions.login(&cookies, user_id).await.re(&re)?;
+
+    Ok(Redirect::to("/"))
+}
+
+async fn logout(re: Render, cookies: Cookies) -> ServeResult<Redirect> {
+    re.app.sessions.logout(&cookies).await.re(&re)?;
+    Ok(Redirect::to("/"))
+}
+
 #[derive(Debug, Clone)]
 struct App {
     pub db: Db,
+    pub sessions: Sessions,
     pub styles: Styles,
     pub themes: Themes,
     pub scripts: Scripts,
+    pub languages: Languages,
     pub reloader: Reloader,
 }
 
@@ -71,6 +112,10 @@ impl Application for App {
         &self.db
     }
 
+    fn sessions(&self) -> &Sessions {
+        &self.sessions
+    }
+
     fn styles(&self) -> &Styles {
         &self.styles
     }
@@ -83,6 +128,10 @@ impl Application for App {
         &self.scripts
     }
 
+    fn languages(&self) -> &Languages {
+        &self.languages
+    }
+
     fn reloader(&self) -> &Reloader {
         &self.reloader
     }
@@ -112,9 +161,9 @@ impl CurrentHooks for Current {
     type Session = Session;
     type User = User;
 
-    async fn session(_db: &Db, _id: Id) -> Result<Option<Self::Session>> {
+    async fn session(_db: &Db, stored: StoredSession) -> Result<Option<Self::Session>> {
         Ok(Some(Session {
-            user_id: Id::new_v4(),
+            user_id: stored.user_id,
         }))
     }
 
@@ -0,0 +1,230 @@
+//! Versioned SQL migrations, read from `db.migrations`.
+//!
+//! Each migration is a pair of files named `<version>_<name>.up.sql` and
+//! `<version>_<name>.down.sql`, where the down file is optional but required
+//! to roll the migration back. Applied versions are recorded in the
+//! `_plethora_migrations` table.
+
+use super::{query, query_scalar, Db};
+use crate::{helper::fs::read_dir_async, stuff::STUFF};
+use anyhow::{bail, Context, Result};
+use camino::Utf8Path;
+use futures::StreamExt;
+use kstring::KString;
+use std::{collections::BTreeMap, pin::pin};
+
+const TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS _plethora_migrations (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+)
+"#;
+
+const UP_EXT: &str = ".up.sql";
+const DOWN_EXT: &str = ".down.sql";
+
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub version: i64,
+    pub name: KString,
+    pub up: String,
+    pub down: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Migrations<'a> {
+    db: &'a Db,
+    dry_run: bool,
+}
+
+impl<'a> Migrations<'a> {
+    pub(super) fn new(db: &'a Db) -> Self {
+        Self { db, dry_run: false }
+    }
+
+    /// Returns the migrations that would run without running them, so the
+    /// caller can show their SQL.
+    pub fn dry_run(self, dry_run: bool) -> Self {
+        Self { dry_run, ..self }
+    }
+
+    pub async fn pending(&self) -> Result<Vec<Migration>> {
+        let applied = self.applied().await?;
+        let migrations = load(&STUFF.db.migrations).await?;
+
+        Ok(migrations
+            .into_values()
+            .filter(|m| !applied.contains(&m.version))
+            .collect())
+    }
+
+    /// Applies every pending migration in version order, returning them.
+    pub async fn up(self) -> Result<Vec<Migration>> {
+        let pending = self.pending().await?;
+        if self.dry_run {
+            return Ok(pending);
+        }
+
+        for migration in &pending {
+            let Migration { version, name, .. } = migration;
+
+            let mut tx = self.db.begin().await?;
+            sqlx::raw_sql(&migration.up)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("failed to apply migration {version}_{name}"))?;
+            query("INSERT INTO _plethora_migrations (version, name) VALUES (?, ?)")
+                .bind(version)
+                .bind(name.as_str())
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+
+            tracing::info!(target: "plethora::db", %version, %name, "migration applied");
+        }
+
+        Ok(pending)
+    }
+
+    /// Reverts the `steps` most recently applied migrations, returning them.
+    pub async fn down(self, steps: usize) -> Result<Vec<Migration>> {
+        let mut applied = self.applied().await?;
+        let mut migrations = load(&STUFF.db.migrations).await?;
+        let mut reverted = Vec::new();
+
+        applied.reverse();
+
+        for version in applied.into_iter().take(steps) {
+            let migration = migrations
+                .remove(&version)
+                .with_context(|| format!("applied migration {version} has no files"))?;
+            let Migration { name, .. } = &migration;
+            let Some(down) = migration.down.as_deref() else {
+                bail!("migration {version}_{name} can not be reverted, it has no down file");
+            };
+
+            if self.dry_run {
+                reverted.push(migration);
+                continue;
+            }
+
+            let mut tx = self.db.begin().await?;
+            sqlx::raw_sql(down)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("failed to revert migration {version}_{name}"))?;
+            query("DELETE FROM _plethora_migrations WHERE version = ?")
+                .bind(version)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+
+            tracing::info!(target: "plethora::db", %version, %name, "migration reverted");
+            reverted.push(migration);
+        }
+
+        Ok(reverted)
+    }
+
+    async fn applied(&self) -> Result<Vec<i64>> {
+        query(TABLE).execute(self.db.pool()).await?;
+
+        let versions = query_scalar("SELECT version FROM _plethora_migrations ORDER BY version")
+            .fetch_all(self.db.pool())
+            .await?;
+
+        Ok(versions)
+    }
+}
+
+async fn load(dir: &Utf8Path) -> Result<BTreeMap<i64, Migration>> {
+    let mut migrations = BTreeMap::<i64, Migration>::new();
+    let mut downs = Vec::new();
+    let mut files = pin!(read_dir_async(dir).await.files());
+
+    while let Some(path) = files.next().await {
+        let Some(file_name) = path.file_name() else {
+            continue;
+        };
+        let Some((version, name, up)) = parse_file_name(file_name) else {
+            continue;
+        };
+
+        let sql = tokio::fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("failed to read {path}"))?;
+
+        if !up {
+            downs.push((version, sql));
+            continue;
+        }
+
+        let migration = Migration {
+            version,
+            name: KString::from_ref(name),
+            up: sql,
+            down: None,
+        };
+
+        if let Some(other) = migrations.insert(version, migration) {
+            bail!("duplicate migration version {version} ({})", other.name);
+        }
+    }
+
+    for (version, sql) in downs {
+        let migration = migrations
+            .get_mut(&version)
+            .with_context(|| format!("down migration {version} has no up migration"))?;
+        migration.down = Some(sql);
+    }
+
+    Ok(migrations)
+}
+
+/// Parses `<version>_<name>.up.sql` or `.down.sql` into its parts, where the
+/// last part is whether it's an up migration.
+fn parse_file_name(file_name: &str) -> Option<(i64, &str, bool)> {
+    let (stem, up) = match file_name.strip_suffix(UP_EXT) {
+        Some(stem) => (stem, true),
+        None => (file_name.strip_suffix(DOWN_EXT)?, false),
+    };
+
+    let (version, name) = stem.split_once('_')?;
+    let version = version.parse().ok()?;
+
+    Some((version, name, up))
+}
+
+impl Db {
+    /// Applies all pending migrations.
+    pub async fn migrate(&self) -> Result<()> {
+        self.migrations().up().await?;
+        Ok(())
+    }
+
+    pub fn migrations(&self) -> Migrations<'_> {
+        Migrations::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names() {
+        use parse_file_name as p;
+
+        assert_eq!(
+            p("0001_create_users.up.sql"),
+            Some((1, "create_users", true))
+        );
+        assert_eq!(
+            p("20240102_add_posts.down.sql"),
+            Some((20240102, "add_posts", false))
+        );
+        assert_eq!(p("0001_create_users.sql"), None);
+        assert_eq!(p("create_users.up.sql"), None);
+    }
+}
@@ -1,13 +1,97 @@
-use anyhow::Result;
+use crate::stuff::STUFF;
+use anyhow::{ensure, Context, Result};
+use futures::future::BoxFuture;
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    Sqlite, SqlitePool, Transaction,
+};
+use std::{str::FromStr, time::Duration};
 use uuid::Uuid;
 
+mod migrate;
+
+pub use migrate::{Migration, Migrations};
+pub use sqlx::{query, query_as, query_scalar, FromRow};
+
 pub type Id = Uuid;
+pub type Pool = SqlitePool;
+pub type Tx = Transaction<'static, Sqlite>;
+
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Debug, Clone)]
-pub struct Db {}
+pub struct Db {
+    pool: Pool,
+}
 
 impl Db {
     pub async fn new() -> Result<Self> {
-        Ok(Self {})
+        Self::connect(&STUFF.db.url).await
+    }
+
+    pub async fn connect(url: &str) -> Result<Self> {
+        let options = SqliteConnectOptions::from_str(url)
+            .with_context(|| format!("invalid database url {url}"))?
+            .create_if_missing(true)
+            .foreign_keys(true);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(STUFF.db.max_connections)
+            .connect_with(options)
+            .await
+            .with_context(|| format!("failed to connect to {url}"))?;
+
+        tracing::debug!(%url, "database connected");
+        Ok(Self { pool })
+    }
+
+    /// The underlying pool, which can be passed as the executor of any
+    /// [`query`], [`query_as`] or [`query_scalar`].
+    pub fn pool(&self) -> &Pool {
+        &self.pool
+    }
+
+    pub async fn begin(&self) -> Result<Tx> {
+        Ok(self.pool.begin().await?)
+    }
+
+    /// Runs `f` inside a transaction, committing if it succeeds and rolling
+    /// back otherwise.
+    ///
+    /// ```ignore
+    /// db.transaction(|tx| Box::pin(async move {
+    ///     query("INSERT INTO posts (id) VALUES (?)").bind(id).execute(&mut **tx).await?;
+    ///     Ok(())
+    /// }))
+    /// .await?;
+    /// ```
+    pub async fn transaction<T, F>(&self, f: F) -> Result<T>
+    where
+        F: for<'t> FnOnce(&'t mut Tx) -> BoxFuture<'t, Result<T>>,
+    {
+        let mut tx = self.begin().await?;
+
+        match f(&mut tx).await {
+            Ok(value) => {
+                tx.commit().await?;
+                Ok(value)
+            }
+            Err(error) => {
+                if let Err(rollback_error) = tx.rollback().await {
+                    tracing::error!("error rolling back transaction: {rollback_error}");
+                }
+                Err(error)
+            }
+        }
+    }
+
+    pub async fn health(&self) -> Result<()> {
+        let ping = query_scalar::<_, i64>("SELECT 1").fetch_one(&self.pool);
+        let one = tokio::time::timeout(HEALTH_TIMEOUT, ping)
+            .await
+            .context("database health check timed out")??;
+
+        ensure!(one == 1, "database health check returned {one}");
+        Ok(())
     }
 }
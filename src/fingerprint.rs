@@ -0,0 +1,226 @@
+//! Content hashed URLs for static files, such as the tailwind and esbuild
+//! outputs under [`scratch::public_dir`]. A file at `/styles/default.css` is
+//! served at `/styles/default.<hash>.css` as well, which can be cached
+//! forever since its name changes whenever its content does.
+
+use crate::{helper::fs::walk_dir_async, reload::Reload, scratch, stuff::STUFF};
+use ahash::AHashMap;
+use anyhow::Result;
+use arc_swap::ArcSwap;
+use camino::Utf8PathBuf;
+use futures::StreamExt;
+use kstring::KString;
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, fmt::Write, future::Future, pin::pin, sync::OnceLock};
+
+const HASH_LEN: usize = 10;
+
+static MANIFEST: OnceLock<AssetManifest> = OnceLock::new();
+
+/// Hashes the static files in the public dirs, and hashes them again
+/// whenever one changes while reloading, be it edited by hand or written by
+/// tailwind or esbuild.
+#[derive(Debug, Clone)]
+pub struct Fingerprints {
+    _private: (),
+}
+
+impl Fingerprints {
+    pub async fn new() -> Result<Self> {
+        AssetManifest::get().refresh().await?;
+        Ok(Self { _private: () })
+    }
+}
+
+impl Reload for Fingerprints {
+    fn dirs(&self) -> Vec<Utf8PathBuf> {
+        let public = STUFF.public.dir.to_path_buf();
+        let public = public.is_dir().then_some(public);
+
+        public.into_iter().chain([scratch::public_dir()]).collect()
+    }
+
+    fn reload(&self, _path: Utf8PathBuf) -> impl Future<Output = Result<()>> + Send + 'static {
+        AssetManifest::get().refresh()
+    }
+}
+
+/// The hashed name of each static file, keyed by its path relative to the
+/// public dirs, as of the last time [`Fingerprints`] hashed them.
+#[derive(Debug, Default)]
+pub struct AssetManifest {
+    entries: ArcSwap<AHashMap<KString, Entry>>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    file: Utf8PathBuf,
+    hash: KString,
+}
+
+/// A static file found from a fingerprinted URL.
+#[derive(Debug, Clone)]
+pub struct Fingerprinted {
+    pub file: Utf8PathBuf,
+    /// Whether the hash in the URL is that of the file's current content, so
+    /// that the response can be cached forever.
+    pub current: bool,
+}
+
+impl AssetManifest {
+    pub fn get() -> &'static Self {
+        MANIFEST.get_or_init(Self::default)
+    }
+
+    /// The fingerprinted URL of a static file, given its unhashed URL, or
+    /// `None` if there's no such file.
+    pub fn url(&self, path: &str) -> Option<String> {
+        let path = path.trim_start_matches('/');
+        let entries = self.entries.load();
+        let entry = entries.get(path)?;
+
+        Some(format!("/{}", hashed_name(path, &entry.hash)))
+    }
+
+    /// Resolves a fingerprinted URL back to the file it names.
+    pub fn resolve(&self, url: &str) -> Option<Fingerprinted> {
+        let (path, hash) = unhashed_name(url.trim_start_matches('/'))?;
+        let entries = self.entries.load();
+        let entry = entries.get(path.as_str())?;
+
+        Some(Fingerprinted {
+            file: entry.file.clone(),
+            current: entry.hash == hash,
+        })
+    }
+
+    /// Each static file and its fingerprinted URL.
+    pub fn snapshot(&self) -> BTreeMap<KString, String> {
+        self.entries
+            .load()
+            .iter()
+            .map(|(path, entry)| (path.clone(), format!("/{}", hashed_name(path, &entry.hash))))
+            .collect()
+    }
+
+    /// Hashes every file in the public dirs, where `public.dir` takes
+    /// precedence over the scratch outputs, as when serving them.
+    async fn refresh(&self) -> Result<()> {
+        let mut entries = AHashMap::new();
+
+        for dir in [STUFF.public.dir.to_path_buf(), scratch::public_dir()] {
+            let mut files = pin!(walk_dir_async(&dir));
+
+            while let Some(file) = files.next().await {
+                let Ok(path) = file.strip_prefix(&dir) else {
+                    continue;
+                };
+                let path = KString::from_ref(path.as_str());
+                if entries.contains_key(&path) {
+                    continue;
+                }
+
+                // A file can be replaced while an output is being written.
+                match tokio::fs::read(&file).await {
+                    Ok(bytes) => {
+                        let hash = content_hash(&bytes);
+                        entries.insert(path, Entry { file, hash });
+                    }
+                    Err(error) => tracing::warn!("error hashing static file {file}: {error}"),
+                }
+            }
+        }
+
+        tracing::debug!(target: "plethora::fingerprint", files = entries.len(), "static files hashed");
+        self.entries.store(entries.into());
+        Ok(())
+    }
+}
+
+pub(crate) fn content_hash(bytes: &[u8]) -> KString {
+    let digest = Sha256::digest(bytes);
+    let mut hash = String::with_capacity(HASH_LEN);
+
+    for byte in digest.iter().take(HASH_LEN / 2) {
+        write!(hash, "{byte:02x}").expect("write to string");
+    }
+
+    KString::from_string(hash)
+}
+
+/// Inserts the hash before the extension, so `styles/default.css` becomes
+/// `styles/default.<hash>.css`.
+fn hashed_name(path: &str, hash: &str) -> String {
+    let (dir, name) = match path.rsplit_once('/') {
+        Some((dir, name)) => (Some(dir), name),
+        None => (None, path),
+    };
+
+    let name = match name.split_once('.') {
+        Some((stem, ext)) => format!("{stem}.{hash}.{ext}"),
+        None => format!("{name}.{hash}"),
+    };
+
+    match dir {
+        Some(dir) => format!("{dir}/{name}"),
+        None => name,
+    }
+}
+
+/// The inverse of [`hashed_name`], returning the unhashed path and the hash.
+fn unhashed_name(path: &str) -> Option<(String, &str)> {
+    let (dir, name) = match path.rsplit_once('/') {
+        Some((dir, name)) => (Some(dir), name),
+        None => (None, path),
+    };
+
+    let mut parts = name.splitn(3, '.');
+    let stem = parts.next()?;
+    let hash = parts.next()?;
+    let ext = parts.next();
+
+    let is_hash = hash.len() == HASH_LEN && hash.bytes().all(|b| b.is_ascii_hexdigit());
+    if !is_hash {
+        return None;
+    }
+
+    let name = match ext {
+        Some(ext) => format!("{stem}.{ext}"),
+        None => stem.to_string(),
+    };
+
+    let path = match dir {
+        Some(dir) => format!("{dir}/{name}"),
+        None => name,
+    };
+
+    Some((path, hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        let hash = "0123456789";
+
+        assert_eq!(
+            hashed_name("styles/default.css", hash),
+            "styles/default.0123456789.css"
+        );
+        assert_eq!(hashed_name("app.min.js", hash), "app.0123456789.min.js");
+        assert_eq!(hashed_name("LICENSE", hash), "LICENSE.0123456789");
+
+        assert_eq!(
+            unhashed_name("styles/default.0123456789.css"),
+            Some(("styles/default.css".to_string(), hash))
+        );
+        assert_eq!(
+            unhashed_name("app.0123456789.min.js"),
+            Some(("app.min.js".to_string(), hash))
+        );
+        assert_eq!(unhashed_name("styles/default.css"), None);
+        assert_eq!(unhashed_name("jquery.min.js"), None);
+    }
+}
@@ -0,0 +1,159 @@
+use ahash::AHashMap;
+use kstring::KString;
+use liquid::{Object, ValueView};
+use toml::{Table, Value};
+
+const PLURAL_KEYS: &[&str] = &["zero", "one", "other"];
+
+#[derive(Debug, Default)]
+pub struct Catalog {
+    messages: AHashMap<KString, Message>,
+}
+
+#[derive(Debug)]
+enum Message {
+    Text(Box<str>),
+    Plural {
+        zero: Option<Box<str>>,
+        one: Option<Box<str>>,
+        other: Box<str>,
+    },
+}
+
+impl Catalog {
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        let table = toml::from_str::<Table>(text)?;
+        let mut this = Self::default();
+
+        this.flatten(String::new(), table);
+        Ok(this)
+    }
+
+    pub fn translate(&self, key: &str, args: &Object) -> Option<String> {
+        let template = match self.messages.get(key)? {
+            Message::Text(text) => text,
+            Message::Plural { zero, one, other } => {
+                let count = args
+                    .get("count")
+                    .and_then(|v| v.as_scalar())
+                    .and_then(|s| s.to_float());
+
+                match count {
+                    Some(0.0) => zero.as_ref().unwrap_or(other),
+                    Some(1.0) => one.as_ref().unwrap_or(other),
+                    _ => other,
+                }
+            }
+        };
+
+        Some(interpolate(template, args))
+    }
+
+    fn flatten(&mut self, prefix: String, table: Table) {
+        for (key, value) in table {
+            let key = if prefix.is_empty() {
+                key
+            } else {
+                format!("{prefix}.{key}")
+            };
+
+            match value {
+                Value::Table(table) if is_plural(&table) => {
+                    let get = |k| match table.get(k) {
+                        Some(Value::String(s)) => Some(Box::from(s.as_str())),
+                        _ => None,
+                    };
+                    let message = Message::Plural {
+                        zero: get("zero"),
+                        one: get("one"),
+                        other: get("other").unwrap_or_default(),
+                    };
+                    self.messages.insert(key.into(), message);
+                }
+                Value::Table(table) => self.flatten(key, table),
+                Value::String(text) => {
+                    self.messages.insert(key.into(), Message::Text(text.into()));
+                }
+                other => {
+                    let text = other.to_string().into();
+                    self.messages.insert(key.into(), Message::Text(text));
+                }
+            }
+        }
+    }
+}
+
+fn is_plural(table: &Table) -> bool {
+    table.contains_key("other") && table.keys().all(|k| PLURAL_KEYS.contains(&k.as_str()))
+}
+
+/// Replaces each `{name}` with the matching argument, leaving unknown
+/// placeholders untouched so missing arguments are visible in the output.
+fn interpolate(template: &str, args: &Object) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        match after.find('}').map(|end| (&after[..end], end)) {
+            Some((name, end)) if args.contains_key(name) => {
+                out.push_str(&args[name].to_kstr());
+                rest = &after[end + 1..];
+            }
+            _ => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use liquid::object;
+
+    const TEXT: &str = r#"
+        greeting = "Hello, {name}!"
+
+        [posts]
+        title = "Posts"
+
+        [posts.reply_count]
+        zero = "No replies"
+        one = "One reply"
+        other = "{count} replies"
+    "#;
+
+    #[test]
+    fn nested_and_interpolated() {
+        let catalog = Catalog::parse(TEXT).unwrap();
+        let t = |key, args| catalog.translate(key, &args);
+
+        assert_eq!(t("posts.title", object!({})).as_deref(), Some("Posts"));
+        assert_eq!(
+            t("greeting", object!({ "name": "Ada" })).as_deref(),
+            Some("Hello, Ada!")
+        );
+        assert_eq!(
+            t("greeting", object!({})).as_deref(),
+            Some("Hello, {name}!")
+        );
+        assert_eq!(t("posts.missing", object!({})), None);
+    }
+
+    #[test]
+    fn plurals() {
+        let catalog = Catalog::parse(TEXT).unwrap();
+        let t = |count: i64| catalog.translate("posts.reply_count", &object!({ "count": count }));
+
+        assert_eq!(t(0).as_deref(), Some("No replies"));
+        assert_eq!(t(1).as_deref(), Some("One reply"));
+        assert_eq!(t(7).as_deref(), Some("7 replies"));
+    }
+}
@@ -0,0 +1,121 @@
+//! Translation catalogs, one TOML file per language in `lang.dir`.
+//!
+//! ```toml
+//! # languages/en.toml
+//! [posts]
+//! title = "Posts by {author}"
+//!
+//! [posts.reply_count]
+//! zero = "No replies"
+//! one = "One reply"
+//! other = "{count} replies"
+//! ```
+//!
+//! Nested tables become dotted keys (`posts.title`), `{name}` is replaced by
+//! the named argument of the same name, and a table made up only of `zero`,
+//! `one` and `other` is a plural message chosen by the `count` argument.
+
+use self::catalog::Catalog;
+use crate::{reload::Reload, stuff::STUFF};
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use dashmap::DashMap;
+use kstring::KString;
+use liquid::Object;
+use std::{future::Future, sync::Arc};
+
+mod catalog;
+
+#[derive(Debug, Clone)]
+pub struct Languages {
+    map: Arc<DashMap<KString, Catalog>>,
+}
+
+impl Languages {
+    pub async fn new() -> Result<Self> {
+        let this = Self {
+            map: Arc::new(DashMap::new()),
+        };
+
+        #[cfg(feature = "langdir")]
+        {
+            use crate::helper::fs::read_dir_async;
+            use futures::StreamExt;
+            use std::pin::pin;
+
+            let mut files = pin!(read_dir_async(&STUFF.lang.dir).await.files());
+            while let Some(path) = files.next().await {
+                this.load(&path).await?;
+            }
+        }
+
+        Ok(this)
+    }
+
+    pub fn contains(&self, language: &str) -> bool {
+        self.map.contains_key(language)
+    }
+
+    /// Translates `key` into `language`, falling back to the default language
+    /// and then to the key itself.
+    pub fn t(&self, language: &str, key: &str, args: &Object) -> String {
+        let found = self
+            .map
+            .get(language)
+            .and_then(|catalog| catalog.translate(key, args))
+            .or_else(|| {
+                let default = STUFF.lang.default.as_str();
+                self.map
+                    .get(default)
+                    .and_then(|catalog| catalog.translate(key, args))
+            });
+
+        found.unwrap_or_else(|| {
+            tracing::debug!(target: "plethora::languages", %language, %key, "missing translation");
+            key.to_string()
+        })
+    }
+
+    async fn load(&self, path: &Utf8Path) -> Result<()> {
+        let Some(language) = language_of(path) else {
+            return Ok(());
+        };
+
+        if !tokio::fs::try_exists(path).await? {
+            self.map.remove(language);
+            tracing::debug!(target: "plethora::languages", %language, "language removed");
+            return Ok(());
+        }
+
+        let text = tokio::fs::read_to_string(path).await?;
+        let catalog = Catalog::parse(&text).with_context(|| format!("failed to parse {path}"))?;
+
+        self.map.insert(KString::from_ref(language), catalog);
+        tracing::debug!(target: "plethora::languages", %language, "language loaded");
+        Ok(())
+    }
+}
+
+#[allow(clippy::manual_async_fn)]
+impl Reload for Languages {
+    #[cfg(feature = "langdir")]
+    fn dirs(&self) -> Vec<Utf8PathBuf> {
+        vec![STUFF.lang.dir.to_path_buf()]
+    }
+
+    #[cfg(not(feature = "langdir"))]
+    fn dirs(&self) -> Vec<Utf8PathBuf> {
+        Vec::new()
+    }
+
+    fn reload(&self, path: Utf8PathBuf) -> impl Future<Output = Result<()>> + Send + 'static {
+        let this = self.clone();
+        async move { this.load(&path).await }
+    }
+}
+
+fn language_of(path: &Utf8Path) -> Option<&str> {
+    (path.extension() == Some("toml"))
+        .then(|| path.file_stem())
+        .flatten()
+}
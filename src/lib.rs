@@ -5,6 +5,7 @@ pub use anyhow as error;
 pub use axum;
 pub use axum::async_trait;
 pub use liquid;
+pub use sqlx;
 pub use tokio;
 pub use tower;
 pub use tower_cookies;
@@ -12,11 +13,14 @@ pub use tower_http;
 
 pub mod binary;
 pub mod db;
+pub mod fingerprint;
 pub mod helper;
+pub mod languages;
 pub mod reload;
 pub mod scratch;
 pub mod scripts;
 pub mod serve;
+pub mod sessions;
 pub mod stuff;
 pub mod styles;
 pub mod themes;
@@ -2,7 +2,7 @@ use self::proc::Proc;
 use crate::stuff::STUFF;
 use anyhow::Result;
 use axum::response::sse::KeepAlive;
-use camino::{Utf8Path, Utf8PathBuf};
+use camino::Utf8PathBuf;
 use std::{cell::OnceCell, future::Future, sync::Arc, time::Duration};
 use tokio::sync::broadcast::{self, error::RecvError};
 
@@ -11,7 +11,8 @@ mod proc;
 const DEBOUNCE: Duration = Duration::from_secs(1);
 
 pub trait Reload: Send + 'static {
-    fn dir(&self) -> Option<&'static Utf8Path>;
+    /// The dirs to watch, which may be none.
+    fn dirs(&self) -> Vec<Utf8PathBuf>;
     fn reload(&self, path: Utf8PathBuf) -> impl Future<Output = Result<()>> + Send + 'static;
 }
 
@@ -50,17 +51,18 @@ pub struct ReloadBuilder {
 impl ReloadBuilder {
     pub fn reload<R: Reload>(mut self, reload: R) -> Self {
         if STUFF.reload {
-            let Some(dir) = reload.dir() else {
+            let dirs = reload.dirs();
+            if dirs.is_empty() {
                 return self;
-            };
+            }
 
             let reloaded = self.reloaded.get_or_init(|| broadcast::channel(1));
             let reloaded_tx = reloaded.0.clone();
 
-            let proc = Proc::new(dir, reload, reloaded_tx)
-                .unwrap_or_else(|_| panic!("failed to watch {dir} for reload"));
+            let proc = Proc::new(&dirs, reload, reloaded_tx)
+                .unwrap_or_else(|_| panic!("failed to watch {dirs:?} for reload"));
 
-            tracing::debug!(%dir, "watching for reload");
+            tracing::debug!(?dirs, "watching for reload");
             self.procs.push(proc);
         }
 
@@ -1,7 +1,7 @@
 use super::{Reload, DEBOUNCE};
 use crate::stuff::STUFF;
 use anyhow::Result;
-use camino::{Utf8Path, Utf8PathBuf};
+use camino::Utf8PathBuf;
 use notify_debouncer_full::{
     new_debouncer,
     notify::{RecommendedWatcher, RecursiveMode::Recursive, Watcher as _},
@@ -17,7 +17,7 @@ pub struct Proc {
 
 impl Proc {
     pub fn new(
-        dir: &'static Utf8Path,
+        dirs: &[Utf8PathBuf],
         reload: impl Reload,
         reloaded_tx: broadcast::Sender<()>,
     ) -> Result<Self> {
@@ -26,20 +26,22 @@ impl Proc {
         let handler = Handler { tx };
         let mut _debouncer = new_debouncer(DEBOUNCE, None, handler)?;
 
-        let path = dir.as_std_path();
+        for dir in dirs {
+            let path = dir.as_std_path();
 
-        _debouncer.watcher().watch(path, Recursive)?;
-        _debouncer.cache().add_root(path, Recursive);
+            _debouncer.watcher().watch(path, Recursive)?;
+            _debouncer.cache().add_root(path, Recursive);
+        }
 
         tokio::spawn(async move {
             while let Ok(path) = rx.recv().await {
-                let span = tracing::debug_span!("reload", dir = %dir);
-                match reload.reload(path).instrument(span).await {
+                let span = tracing::debug_span!("reload", %path);
+                match reload.reload(path.clone()).instrument(span).await {
                     Ok(()) => {
                         reloaded_tx.send(()).ok();
                     }
                     Err(error) => {
-                        tracing::warn!(target: "plethora::reload", %dir, "reload error: {error}");
+                        tracing::warn!(target: "plethora::reload", %path, "reload error: {error}");
                     }
                 }
             }
@@ -35,6 +35,16 @@ pub(crate) fn tailwind_output_path(slug: &str) -> Utf8PathBuf {
         .with_extension("css")
 }
 
+/// The URL of a theme's tailwind output, before fingerprinting.
+pub(crate) fn tailwind_output_url(slug: &str) -> String {
+    format!("/{PUBLIC_CSS_DIR}/{slug}.css")
+}
+
+/// The URL of an esbuild output, before fingerprinting.
+pub(crate) fn esbuild_output_url(script: &str) -> String {
+    format!("/{PUBLIC_JS_DIR}/{script}")
+}
+
 fn dir() -> &'static Utf8Path {
     &STUFF.scratch.dir
 }
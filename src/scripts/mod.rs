@@ -29,8 +29,8 @@ impl Scripts {
 }
 
 impl Reload for Scripts {
-    fn dir(&self) -> Option<&'static Utf8Path> {
-        Some(&STUFF.scripts.dir)
+    fn dirs(&self) -> Vec<Utf8PathBuf> {
+        vec![STUFF.scripts.dir.to_path_buf()]
     }
 
     fn reload(&self, _path: Utf8PathBuf) -> impl Future<Output = Result<()>> + Send + 'static {
@@ -1,12 +1,23 @@
-use crate::{db::Db, reload::Reloader, scripts::Scripts, styles::Styles, themes::Themes};
+use crate::{
+    db::Db,
+    languages::Languages,
+    reload::Reloader,
+    scripts::Scripts,
+    sessions::Sessions,
+    styles::Styles,
+    themes::{Themes, TitleFormat},
+};
+use anyhow::{Context, Result};
 use axum::{extract::FromRequestParts, http::request::Parts};
 use std::convert::Infallible;
 
 pub trait Application: Clone + Send + Sync + 'static {
     fn db(&self) -> &Db;
+    fn sessions(&self) -> &Sessions;
     fn styles(&self) -> &Styles;
     fn themes(&self) -> &Themes;
     fn scripts(&self) -> &Scripts;
+    fn languages(&self) -> &Languages;
     fn reloader(&self) -> &Reloader;
 
     fn default_theme_slug(&self) -> &str;
@@ -14,6 +25,21 @@ pub trait Application: Clone + Send + Sync + 'static {
     fn base_page_title(&self) -> Option<&str> {
         None
     }
+
+    /// How page titles are put together with the base title, unless the
+    /// theme's manifest says otherwise.
+    fn title_format(&self) -> Option<&TitleFormat> {
+        None
+    }
+
+    /// Checks that the application is ready to serve, which
+    /// [`public_router`](crate::serve::public_router) does before building
+    /// its routes.
+    fn validate(&self) -> Result<()> {
+        self.themes()
+            .ensure(self.default_theme_slug())
+            .context("invalid default theme")
+    }
 }
 
 macro_rules! app_accessors {
@@ -33,7 +59,9 @@ macro_rules! app_accessors {
 
 app_accessors! {
     db: Db,
+    sessions: Sessions,
     styles: Styles,
     themes: Themes,
+    languages: Languages,
     reloader: Reloader,
 }
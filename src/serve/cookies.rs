@@ -0,0 +1,154 @@
+use crate::stuff::STUFF;
+use std::sync::OnceLock;
+use tower_cookies::{Cookie, Cookies, Key};
+
+static KEYS: OnceLock<Keys> = OnceLock::new();
+
+struct Keys {
+    current: Key,
+    old: Box<[Key]>,
+}
+
+impl Keys {
+    fn get() -> &'static Self {
+        KEYS.get_or_init(|| {
+            let current = match &STUFF.cookies.secret {
+                Some(secret) => Key::derive_from(secret.as_bytes()),
+                None => {
+                    tracing::warn!("no cookies.secret configured, using a random key");
+                    Key::generate()
+                }
+            };
+            let old = STUFF
+                .cookies
+                .old_secrets
+                .iter()
+                .map(|secret| Key::derive_from(secret.as_bytes()))
+                .collect();
+
+            Self { current, old }
+        })
+    }
+
+    /// Tries the current key first, then each of the old ones, so cookies
+    /// written before a key rotation remain readable until they're written
+    /// again.
+    fn find(&self, f: impl Fn(&Key) -> Option<Cookie<'static>>) -> Option<Verified> {
+        if let Some(cookie) = f(&self.current) {
+            return Some(Verified {
+                cookie,
+                stale: false,
+            });
+        }
+
+        let cookie = self.old.iter().find_map(f)?;
+        Some(Verified {
+            cookie,
+            stale: true,
+        })
+    }
+
+    fn get_private(&self, cookies: &Cookies, name: &str) -> Option<Verified> {
+        self.find(|key| cookies.private(key).get(name))
+    }
+
+    fn add_private(&self, cookies: &Cookies, cookie: Cookie<'static>) {
+        cookies.private(&self.current).add(cookie);
+    }
+
+    fn get_signed(&self, cookies: &Cookies, name: &str) -> Option<Verified> {
+        self.find(|key| cookies.signed(key).get(name))
+    }
+
+    fn add_signed(&self, cookies: &Cookies, cookie: Cookie<'static>) {
+        cookies.signed(&self.current).add(cookie);
+    }
+}
+
+/// A cookie that passed verification.
+#[derive(Debug)]
+pub struct Verified {
+    pub cookie: Cookie<'static>,
+    /// Whether it was written with one of `cookies.old_secrets`. The reader
+    /// should write it again, with its usual attributes, so that the old
+    /// secret can be dropped without losing it.
+    pub stale: bool,
+}
+
+/// Access to cookies that are signed or encrypted with `cookies.secret`.
+/// Cookies that fail verification are treated as absent.
+pub trait CookiesExt {
+    /// Reads a cookie that is encrypted, so its value is neither readable
+    /// nor forgeable by the client.
+    fn get_private(&self, name: &str) -> Option<Verified>;
+    fn add_private(&self, cookie: Cookie<'static>);
+
+    /// Reads a cookie that is signed, so its value is readable but not
+    /// forgeable by the client.
+    fn get_signed(&self, name: &str) -> Option<Verified>;
+    fn add_signed(&self, cookie: Cookie<'static>);
+}
+
+impl CookiesExt for Cookies {
+    fn get_private(&self, name: &str) -> Option<Verified> {
+        Keys::get().get_private(self, name)
+    }
+
+    fn add_private(&self, cookie: Cookie<'static>) {
+        Keys::get().add_private(self, cookie);
+    }
+
+    fn get_signed(&self, name: &str) -> Option<Verified> {
+        Keys::get().get_signed(self, name)
+    }
+
+    fn add_signed(&self, cookie: Cookie<'static>) {
+        Keys::get().add_signed(self, cookie);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys() -> Keys {
+        Keys {
+            current: Key::generate(),
+            old: Box::new([Key::generate()]),
+        }
+    }
+
+    #[test]
+    fn rewrites_private_under_the_current_key() {
+        let keys = keys();
+        let cookies = Cookies::default();
+        cookies
+            .private(&keys.old[0])
+            .add(Cookie::new("session", "1"));
+
+        let verified = keys.get_private(&cookies, "session").unwrap();
+        assert!(verified.stale);
+        keys.add_private(&cookies, verified.cookie);
+
+        let verified = keys.get_private(&cookies, "session").unwrap();
+        assert!(!verified.stale);
+        assert_eq!(verified.cookie.value(), "1");
+    }
+
+    #[test]
+    fn rewrites_signed_under_the_current_key() {
+        let keys = keys();
+        let cookies = Cookies::default();
+        cookies
+            .signed(&keys.old[0])
+            .add(Cookie::new("theme", "dark"));
+
+        let verified = keys.get_signed(&cookies, "theme").unwrap();
+        assert!(verified.stale);
+        keys.add_signed(&cookies, verified.cookie);
+
+        let verified = keys.get_signed(&cookies, "theme").unwrap();
+        assert!(!verified.stale);
+        assert_eq!(verified.cookie.value(), "dark");
+    }
+}
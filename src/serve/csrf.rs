@@ -0,0 +1,83 @@
+use super::{Application, CurrentState, Renderer, ServeError};
+use anyhow::anyhow;
+use axum::{
+    body::{to_bytes, Body},
+    extract::{FromRequestParts, Request},
+    http::{header, request::Parts, HeaderName, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use url::form_urlencoded;
+
+const HEADER: HeaderName = HeaderName::from_static("x-csrf-token");
+const FIELD: &str = "_csrf";
+const FORM_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Rejects unsafe requests that don't carry the current CSRF token, either in
+/// an `x-csrf-token` header or in the `_csrf` field of a urlencoded form.
+/// Must be layered inside [`current`](super::current).
+///
+/// Multipart bodies aren't read, since that would buffer uploads, so
+/// multipart forms must send the token in the header.
+pub async fn csrf<R, A>(app: A, request: Request, next: Next) -> Response
+where
+    R: Renderer<App = A> + FromRequestParts<A>,
+    A: Application,
+{
+    if is_safe(request.method()) {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let (submitted, body) = match submitted_token(&parts, body).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    let csrf = CurrentState::<R::Current>::extension(&parts.extensions).csrf;
+    if submitted.is_some_and(|token| csrf.verify(&token)) {
+        return next.run(Request::from_parts(parts, body)).await;
+    }
+
+    let re = match R::from_request_parts(&mut parts, &app).await {
+        Ok(re) => re,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    let error = anyhow!("CSRF token missing or invalid");
+    ServeError::status_with_error(re, StatusCode::FORBIDDEN, error).into_response()
+}
+
+fn is_safe(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+async fn submitted_token(parts: &Parts, body: Body) -> Result<(Option<String>, Body), Response> {
+    if let Some(token) = parts.headers.get(HEADER) {
+        let token = token.to_str().ok().map(ToString::to_string);
+        return Ok((token, body));
+    }
+
+    let is_form = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/x-www-form-urlencoded"));
+
+    if !is_form {
+        return Ok((None, body));
+    }
+
+    let bytes = to_bytes(body, FORM_LIMIT)
+        .await
+        .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE.into_response())?;
+
+    let token = form_urlencoded::parse(&bytes)
+        .find(|(key, _)| key == FIELD)
+        .map(|(_, value)| value.into_owned());
+
+    Ok((token, Body::from(bytes)))
+}
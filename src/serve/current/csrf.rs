@@ -0,0 +1,78 @@
+use crate::{serve::CookiesExt, sessions, stuff::STUFF};
+use std::{marker::PhantomData, sync::Arc};
+use tower_cookies::{cookie::SameSite, Cookie, Cookies};
+use uuid::Uuid;
+
+const COOKIE: &str = "plethora-csrf";
+
+#[derive(Debug)]
+pub struct CurrentCsrfState<C> {
+    token: Arc<str>,
+    _cur: PhantomData<C>,
+}
+
+impl<C> CurrentCsrfState<C> {
+    /// Reads the token from the CSRF cookie, which records the session it
+    /// was issued for. A token issued for another session, such as before
+    /// logging in, is replaced rather than carried over.
+    pub(super) fn new(cookies: &Cookies) -> Self {
+        let session = sessions::cookie_id(cookies)
+            .map(|id| id.simple().to_string())
+            .unwrap_or_default();
+
+        let issued = cookies.get_private(COOKIE).and_then(|verified| {
+            let (bound, token) = verified.cookie.value().split_once(':')?;
+            let token = (bound == session).then(|| Arc::<str>::from(token))?;
+
+            if verified.stale {
+                issue(cookies, &session, &token);
+            }
+            Some(token)
+        });
+
+        let token = match issued {
+            Some(token) => token,
+            None => {
+                let token = Uuid::new_v4().simple().to_string();
+                issue(cookies, &session, &token);
+                Arc::from(token)
+            }
+        };
+
+        Self {
+            token,
+            _cur: PhantomData,
+        }
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Compares without short-circuiting, so the comparison doesn't leak how
+    /// much of the token was guessed correctly.
+    pub fn verify(&self, submitted: &str) -> bool {
+        let (a, b) = (self.token.as_bytes(), submitted.as_bytes());
+        a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+    }
+}
+
+impl<C> Clone for CurrentCsrfState<C> {
+    fn clone(&self) -> Self {
+        Self {
+            token: self.token.clone(),
+            _cur: self._cur,
+        }
+    }
+}
+
+/// Writes the CSRF cookie with `token`, bound to `session`.
+fn issue(cookies: &Cookies, session: &str, token: &str) {
+    let cookie = Cookie::build((COOKIE, format!("{session}:{token}")))
+        .path("/")
+        .http_only(true)
+        .secure(STUFF.session.secure)
+        .same_site(SameSite::Lax);
+
+    cookies.add_private(cookie.into());
+}
@@ -0,0 +1,111 @@
+use axum::{extract::Request, http::header};
+use std::marker::PhantomData;
+
+/// What a response can be rendered as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Html,
+    Json,
+}
+
+/// The format the request prefers according to its `Accept` header. Browsers
+/// and clients that accept anything get HTML, so JSON has to be preferred
+/// outright.
+#[derive(Debug)]
+pub struct CurrentFormatState<C> {
+    format: Format,
+    _cur: PhantomData<C>,
+}
+
+impl<C> CurrentFormatState<C> {
+    pub(super) fn new(request: &Request) -> Self {
+        let format = request
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .map_or(Format::Html, negotiate);
+
+        Self {
+            format,
+            _cur: PhantomData,
+        }
+    }
+
+    pub fn get(&self) -> Format {
+        self.format
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.format == Format::Json
+    }
+}
+
+impl<C> Clone for CurrentFormatState<C> {
+    fn clone(&self) -> Self {
+        Self {
+            format: self.format,
+            _cur: self._cur,
+        }
+    }
+}
+
+/// Weighs each format by the quality of the most specific media range that
+/// matches it.
+fn negotiate(accept: &str) -> Format {
+    // (specificity, quality)
+    let mut html = (0, 0.0);
+    let mut json = (0, 0.0);
+
+    for range in accept.split(',') {
+        let mut params = range.split(';');
+        let media = params
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let quality = params
+            .find_map(|p| p.trim().strip_prefix("q="))
+            .and_then(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+
+        let (html_specificity, json_specificity) = match media.as_str() {
+            "text/html" | "application/xhtml+xml" => (3, 0),
+            "application/json" | "application/problem+json" => (0, 3),
+            "text/*" => (2, 0),
+            "application/*" => (0, 2),
+            "*/*" => (1, 1),
+            _ => (0, 0),
+        };
+
+        if html_specificity > html.0 {
+            html = (html_specificity, quality);
+        }
+        if json_specificity > json.0 {
+            json = (json_specificity, quality);
+        }
+    }
+
+    match json.1 > html.1 {
+        true => Format::Json,
+        false => Format::Html,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiates() {
+        let browser = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+
+        assert_eq!(negotiate(browser), Format::Html);
+        assert_eq!(negotiate("*/*"), Format::Html);
+        assert_eq!(negotiate("application/json"), Format::Json);
+        assert_eq!(negotiate("application/json, */*;q=0.5"), Format::Json);
+        assert_eq!(negotiate("text/html;q=0.5, application/json"), Format::Json);
+        assert_eq!(negotiate("text/html, application/json"), Format::Html);
+        assert_eq!(negotiate("application/*, text/html;q=0.1"), Format::Json);
+        assert_eq!(negotiate("application/json;q=0"), Format::Html);
+    }
+}
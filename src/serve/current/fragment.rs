@@ -0,0 +1,79 @@
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderName},
+};
+use kstring::KString;
+use std::marker::PhantomData;
+
+/// Set by clients other than htmx and Turbo to ask for a fragment, naming it
+/// or left empty for the whole template.
+const FRAGMENT: HeaderName = HeaderName::from_static("plethora-fragment");
+const HX_REQUEST: HeaderName = HeaderName::from_static("hx-request");
+const HX_TARGET: HeaderName = HeaderName::from_static("hx-target");
+const HX_BOOSTED: HeaderName = HeaderName::from_static("hx-boosted");
+const HX_HISTORY_RESTORE: HeaderName = HeaderName::from_static("hx-history-restore-request");
+const TURBO_FRAME: HeaderName = HeaderName::from_static("turbo-frame");
+
+/// Whether the request asks for a template without its layout, as htmx and
+/// Turbo frames do, and which `{% fragment %}` of it if any.
+#[derive(Debug)]
+pub struct CurrentFragmentState<C> {
+    requested: bool,
+    name: Option<KString>,
+    _cur: PhantomData<C>,
+}
+
+impl<C> CurrentFragmentState<C> {
+    pub(super) fn new(request: &Request) -> Self {
+        let headers = request.headers();
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(KString::from_ref)
+        };
+
+        let (requested, name) = if let Some(name) = header(FRAGMENT) {
+            (true, Some(name))
+        } else if let Some(frame) = header(TURBO_FRAME) {
+            (true, Some(frame))
+        } else if is_htmx_swap(headers) {
+            (true, header(HX_TARGET))
+        } else {
+            (false, None)
+        };
+
+        Self {
+            requested,
+            name: name.filter(|name| !name.is_empty()),
+            _cur: PhantomData,
+        }
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested
+    }
+
+    /// The fragment to render, which falls back to the whole template when
+    /// the template doesn't define it.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+impl<C> Clone for CurrentFragmentState<C> {
+    fn clone(&self) -> Self {
+        Self {
+            requested: self.requested,
+            name: self.name.clone(),
+            _cur: self._cur,
+        }
+    }
+}
+
+/// Boosted links and history restores replace the whole page, so they still
+/// get the layout.
+fn is_htmx_swap(headers: &HeaderMap) -> bool {
+    let is_true = |name| headers.get(name).is_some_and(|v| v == "true");
+    is_true(HX_REQUEST) && !is_true(HX_BOOSTED) && !is_true(HX_HISTORY_RESTORE)
+}
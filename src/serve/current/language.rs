@@ -1,30 +1,177 @@
-use std::marker::PhantomData;
+use super::CurrentHooks;
+use crate::stuff::STUFF;
+use axum::{
+    extract::{Query, Request},
+    http::header,
+};
+use kstring::KString;
+use serde::{Deserialize, Serialize};
+use std::{marker::PhantomData, ops::Deref};
+use tower_cookies::Cookies;
+
+const COOKIE: &str = "plethora-lang";
 
-// TODO
 #[derive(Debug)]
 pub struct CurrentLanguageState<C> {
+    tag: KString,
     _cur: PhantomData<C>,
 }
 
-impl<C> CurrentLanguageState<C> {
-    pub(super) fn new() -> Self {
-        Self { _cur: PhantomData }
+impl<C: CurrentHooks> CurrentLanguageState<C> {
+    pub(super) fn new(request: &Request, cookies: &Cookies) -> Self {
+        let tag = get_tag(request, cookies, &STUFF.lang.supported)
+            .unwrap_or_else(|| STUFF.lang.default.clone());
+
+        Self {
+            tag,
+            _cur: PhantomData,
+        }
+    }
+
+    pub fn with_fixed_language(tag: &str) -> Self {
+        Self {
+            tag: KString::from_ref(tag),
+            _cur: PhantomData,
+        }
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn get(&self) -> CurrentLanguage<C> {
+        CurrentLanguage {
+            tag: self.tag.clone(),
+            _cur: PhantomData,
+        }
     }
 }
 
 impl<C> Clone for CurrentLanguageState<C> {
     fn clone(&self) -> Self {
-        Self { _cur: self._cur }
+        Self {
+            tag: self.tag.clone(),
+            _cur: self._cur,
+        }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
 pub struct CurrentLanguage<C> {
+    tag: KString,
+    #[serde(skip)]
     _cur: PhantomData<C>,
 }
 
 impl<C> Clone for CurrentLanguage<C> {
     fn clone(&self) -> Self {
-        Self { _cur: self._cur }
+        Self {
+            tag: self.tag.clone(),
+            _cur: self._cur,
+        }
+    }
+}
+
+impl<C> Deref for CurrentLanguage<C> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tag
+    }
+}
+
+fn get_tag(request: &Request, cookies: &Cookies, supported: &[KString]) -> Option<KString> {
+    #[derive(Deserialize)]
+    struct QueryLang {
+        lang: String,
+    }
+
+    if let Ok(Query(q)) = Query::<QueryLang>::try_from_uri(request.uri()) {
+        if let Some(tag) = negotiate(&q.lang, supported) {
+            return Some(tag.clone());
+        }
+    }
+
+    if let Some(cookie) = cookies.get(COOKIE) {
+        if let Some(tag) = negotiate(cookie.value(), supported) {
+            return Some(tag.clone());
+        }
+    }
+
+    let accept = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)?
+        .to_str()
+        .ok()?;
+    accept_language(accept)
+        .into_iter()
+        .find_map(|tag| negotiate(tag, supported))
+        .cloned()
+}
+
+/// Matches a requested language tag against the supported ones, first exactly
+/// and then by primary subtag, so that `en-GB` can still be served `en`.
+fn negotiate<'a>(requested: &str, supported: &'a [KString]) -> Option<&'a KString> {
+    fn primary(tag: &str) -> &str {
+        tag.split(['-', '_']).next().unwrap_or(tag)
+    }
+
+    supported
+        .iter()
+        .find(|tag| tag.eq_ignore_ascii_case(requested))
+        .or_else(|| {
+            let requested = primary(requested);
+            supported
+                .iter()
+                .find(|tag| primary(tag).eq_ignore_ascii_case(requested))
+        })
+}
+
+/// Parses an `Accept-Language` header into its tags, most preferred first.
+fn accept_language(header: &str) -> Vec<&str> {
+    let mut tags = header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let tag = parts.next()?.trim();
+            let q = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+
+            (!tag.is_empty() && tag != "*" && q > 0.0).then_some((tag, q))
+        })
+        .collect::<Vec<_>>();
+
+    tags.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    tags.into_iter().map(|(tag, _)| tag).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_language_order() {
+        assert_eq!(
+            accept_language("fr;q=0.5, en-GB, de;q=0.8, *;q=0.1, es;q=0"),
+            vec!["en-GB", "de", "fr"]
+        );
+    }
+
+    #[test]
+    fn negotiate_subtags() {
+        let supported = [KString::from_static("en"), KString::from_static("pt-BR")];
+
+        assert_eq!(negotiate("EN", &supported).map(|t| t.as_str()), Some("en"));
+        assert_eq!(
+            negotiate("en-GB", &supported).map(|t| t.as_str()),
+            Some("en")
+        );
+        assert_eq!(
+            negotiate("pt", &supported).map(|t| t.as_str()),
+            Some("pt-BR")
+        );
+        assert_eq!(negotiate("de", &supported), None);
     }
 }
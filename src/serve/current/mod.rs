@@ -1,5 +1,8 @@
 use super::Application;
-use crate::db::{Db, Id};
+use crate::{
+    db::{Db, Id},
+    sessions::StoredSession,
+};
 use anyhow::Result;
 use axum::{
     extract::{FromRequestParts, Request},
@@ -11,29 +14,46 @@ use serde::Serialize;
 use std::{convert::Infallible, fmt};
 use tower_cookies::Cookies;
 
+mod csrf;
+mod format;
+mod fragment;
 mod language;
 mod session;
 mod theme;
 mod user;
 
+pub use csrf::CurrentCsrfState;
+pub use format::{CurrentFormatState, Format};
+pub use fragment::CurrentFragmentState;
 pub use language::{CurrentLanguage, CurrentLanguageState};
 pub use session::{CurrentSession, CurrentSessionState};
 pub use theme::CurrentThemeState;
 pub use user::{CurrentUser, CurrentUserState};
 
+pub(super) use theme::set_cookie as set_theme_cookie;
+
 pub trait CurrentHooks: fmt::Debug + Clone + Send + Sync + 'static {
     type Session: fmt::Debug + Serialize + Send + Sync + 'static;
     type User: fmt::Debug + Serialize + Send + Sync + 'static;
 
-    async fn session(db: &Db, id: Id) -> Result<Option<Self::Session>>;
+    async fn session(db: &Db, stored: StoredSession) -> Result<Option<Self::Session>>;
     async fn user(db: &Db, id: Id) -> Result<Option<Self::User>>;
 
     fn user_id(session: &Self::Session) -> Id;
+
+    /// The theme the user has chosen for themselves, consulted after the
+    /// `?theme=` query and the theme cookie.
+    fn preferred_theme(_user: &Self::User) -> Option<&str> {
+        None
+    }
 }
 
 #[derive(Debug, Clone)]
 #[non_exhaustive]
 pub struct CurrentState<C: CurrentHooks> {
+    pub csrf: CurrentCsrfState<C>,
+    pub format: CurrentFormatState<C>,
+    pub fragment: CurrentFragmentState<C>,
     pub language: CurrentLanguageState<C>,
     pub session: CurrentSessionState<C>,
     pub theme: CurrentThemeState<C>,
@@ -85,6 +105,9 @@ macro_rules! current_accessors {
 
 current_accessors! {
     <C>
+    csrf: CurrentCsrfState<C>,
+    format: CurrentFormatState<C>,
+    fragment: CurrentFragmentState<C>,
     language: CurrentLanguageState<C>,
     session: CurrentSessionState<C>,
     theme: CurrentThemeState<C>,
@@ -97,11 +120,17 @@ pub async fn current<C: CurrentHooks, A: Application>(
     mut request: Request,
     next: Next,
 ) -> Result<Response, Infallible> {
-    let language = CurrentLanguageState::new();
+    let csrf = CurrentCsrfState::new(&cookies);
+    let format = CurrentFormatState::new(&request);
+    let fragment = CurrentFragmentState::new(&request);
+    let language = CurrentLanguageState::new(&request, &cookies);
     let session = CurrentSessionState::new(&app, &cookies).await;
-    let theme = CurrentThemeState::new(&app, &request, &cookies);
     let user = CurrentUserState::new(&app, session.user_id()).await;
+    let theme = CurrentThemeState::new(&app, &request, &cookies, &user);
     let current = CurrentState::<C> {
+        csrf,
+        format,
+        fragment,
         language,
         session,
         theme,
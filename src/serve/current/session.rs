@@ -4,23 +4,21 @@ use serde::Serialize;
 use std::{ops::Deref, sync::Arc};
 use tower_cookies::Cookies;
 
-const COOKIE: &str = "plethora-session";
-
 #[derive(Debug)]
 pub struct CurrentSessionState<C: CurrentHooks>(Option<Arc<C::Session>>);
 
 impl<C: CurrentHooks> CurrentSessionState<C> {
     pub(super) async fn new(app: &impl Application, cookies: &Cookies) -> Self {
-        let Some(cookie) = cookies.get(COOKIE) else {
-            return Self(None);
-        };
-
-        let Ok(session_id) = Id::parse_str(cookie.value()) else {
-            tracing::debug!(value = %cookie.value(), "malformed session cookie");
-            return Self(None);
+        let stored = match app.sessions().resume(cookies).await {
+            Ok(Some(stored)) => stored,
+            Ok(None) => return Self(None),
+            Err(error) => {
+                tracing::error!("error resuming current session: {error}");
+                return Self(None);
+            }
         };
 
-        match C::session(app.db(), session_id).await {
+        match C::session(app.db(), stored).await {
             Ok(session) => Self(session.map(Arc::new)),
             Err(error) => {
                 tracing::error!("error resolving current session: {error}");
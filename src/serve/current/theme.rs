@@ -1,12 +1,17 @@
-use super::{Application, CurrentHooks};
+use super::{Application, CurrentHooks, CurrentUserState};
+use crate::serve::CookiesExt;
 use crate::themes::{ThemeGuard, Themes};
 use anyhow::{Context, Result};
 use axum::extract::{Query, Request};
 use serde::Deserialize;
 use std::{marker::PhantomData, sync::Arc};
-use tower_cookies::Cookies;
+use tower_cookies::{
+    cookie::{time::Duration, SameSite},
+    Cookie, Cookies,
+};
 
 const COOKIE: &str = "plethora-theme";
+const MAX_AGE: Duration = Duration::days(365);
 
 #[derive(Debug)]
 pub struct CurrentThemeState<C> {
@@ -15,13 +20,25 @@ pub struct CurrentThemeState<C> {
 }
 
 impl<C: CurrentHooks> CurrentThemeState<C> {
-    pub(super) fn new(app: &impl Application, request: &Request, cookies: &Cookies) -> Self {
+    pub(super) fn new(
+        app: &impl Application,
+        request: &Request,
+        cookies: &Cookies,
+        user: &CurrentUserState<C>,
+    ) -> Self {
         let themes = &app.themes();
+        let user = user.get();
+        let preferred_slug = user.as_deref().and_then(C::preferred_theme);
         let current_slug = get_slug(request, cookies);
         let slug = current_slug
             .and_then(|slug| themes.get(&slug))
+            .or_else(|| preferred_slug.and_then(|slug| themes.get(slug)))
             .or_else(|| themes.get(app.default_theme_slug()))
-            .expect("no themes")
+            .unwrap_or_else(|| {
+                let default = app.default_theme_slug();
+                tracing::warn!(%default, "default theme not loaded, using the fallback theme");
+                themes.fallback()
+            })
             .slug()
             .as_str()
             .into();
@@ -69,9 +86,24 @@ fn get_slug(request: &Request, cookies: &Cookies) -> Option<String> {
         return Some(q.theme);
     }
 
-    if let Some(cookie) = cookies.get(COOKIE) {
-        return Some(cookie.value().to_string());
+    if let Some(verified) = cookies.get_signed(COOKIE) {
+        let slug = verified.cookie.value().to_string();
+        if verified.stale {
+            set_cookie(cookies, &slug);
+        }
+        return Some(slug);
     }
 
     None
 }
+
+/// Remembers `slug` as the chosen theme for later requests.
+pub(in crate::serve) fn set_cookie(cookies: &Cookies, slug: &str) {
+    let cookie = Cookie::build((COOKIE, slug.to_string()))
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .max_age(MAX_AGE);
+
+    cookies.add_signed(cookie.into());
+}
@@ -1,38 +1,109 @@
+use self::problem::Problem;
 use super::Renderer;
 use anyhow::Error;
-use axum::response::{IntoResponse, Response};
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
 
 mod fallback;
+mod problem;
 mod traits;
 
 pub type ServeResult<T = Response> = Result<T, ServeError>;
 pub use traits::*;
 
+/// An error rendered with the theme's error pages, or as problem details for
+/// requests that prefer JSON.
 #[derive(Debug)]
 pub struct ServeError {
-    response: Response,
+    kind: Kind,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Response(Box<Response>),
+    Problem(Problem),
 }
 
 impl ServeError {
     pub fn new(re: impl Renderer, error: Error) -> Self {
-        let response = match re.try_render_error(&error) {
+        Self::status_with_error(re, StatusCode::INTERNAL_SERVER_ERROR, error)
+    }
+
+    pub fn not_found(re: impl Renderer) -> Self {
+        Self::status(re, StatusCode::NOT_FOUND)
+    }
+
+    pub fn forbidden(re: impl Renderer) -> Self {
+        Self::status(re, StatusCode::FORBIDDEN)
+    }
+
+    pub fn unauthorized(re: impl Renderer) -> Self {
+        Self::status(re, StatusCode::UNAUTHORIZED)
+    }
+
+    /// Renders the theme's page for `status`.
+    pub fn status(re: impl Renderer, status: StatusCode) -> Self {
+        if re.current().format.is_json() {
+            return Self::problem(status, None);
+        }
+
+        match re.try_render_status(status, None) {
+            Ok(response) => Self::response(response),
+            Err(error) => Self::new(re, error),
+        }
+    }
+
+    /// Renders the theme's page for `status`, with `error` as its message.
+    pub fn status_with_error(re: impl Renderer, status: StatusCode, error: Error) -> Self {
+        if re.current().format.is_json() {
+            return Self::problem(status, Some(&error));
+        }
+
+        let response = match re.try_render_status(status, Some(&error)) {
             Ok(response) => response,
             Err(new_error) => fallback::render(error, new_error),
         };
 
-        Self { response }
+        Self::response(response)
     }
 
-    pub fn not_found(re: impl Renderer) -> Self {
-        match re.try_render_not_found() {
-            Ok(response) => Self { response },
-            Err(error) => Self::new(re, error),
+    fn response(response: Response) -> Self {
+        Self {
+            kind: Kind::Response(Box::new(response)),
+        }
+    }
+
+    fn problem(status: StatusCode, error: Option<&Error>) -> Self {
+        Self {
+            kind: Kind::Problem(Problem::new(status, error)),
+        }
+    }
+}
+
+impl ServeError {
+    /// Changes the status of the response without rendering another page,
+    /// unlike [`status`](Self::status).
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        match &mut self.kind {
+            Kind::Response(response) => *response.status_mut() = status,
+            Kind::Problem(problem) => problem.set_status(status),
         }
+        self
     }
 }
 
 impl IntoResponse for ServeError {
     fn into_response(self) -> Response {
-        self.response
+        let mut response = match self.kind {
+            Kind::Response(response) => *response,
+            Kind::Problem(problem) => problem.into_response(),
+        };
+
+        response
+            .headers_mut()
+            .append(header::VARY, HeaderValue::from_static("accept"));
+        response
     }
 }
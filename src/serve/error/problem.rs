@@ -0,0 +1,57 @@
+use crate::stuff::STUFF;
+use anyhow::Error;
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+const CONTENT_TYPE: HeaderValue = HeaderValue::from_static("application/problem+json");
+const GENERIC_DETAIL: &str = "The request failed with an error.";
+
+/// An error as RFC 9457 problem details, for clients that asked for JSON.
+#[derive(Debug, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    title: &'static str,
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl Problem {
+    /// The detail is the whole chain of the error while reloading, and
+    /// otherwise only says there was one, as errors may hold anything.
+    pub fn new(status: StatusCode, error: Option<&Error>) -> Self {
+        let detail = error.map(|error| match STUFF.reload {
+            true => format!("{error:#}"),
+            false => GENERIC_DETAIL.to_string(),
+        });
+
+        Self {
+            kind: "about:blank",
+            title: status.canonical_reason().unwrap_or_default(),
+            status: status.as_u16(),
+            detail,
+        }
+    }
+
+    pub fn set_status(&mut self, status: StatusCode) {
+        self.title = status.canonical_reason().unwrap_or_default();
+        self.status = status.as_u16();
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut response = (status, Json(self)).into_response();
+
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, CONTENT_TYPE);
+        response
+    }
+}
@@ -44,3 +44,27 @@ impl<T> OrNotFound<T> for Option<T> {
         }
     }
 }
+
+/// Rejects a request with the theme's 403 page, such as when a lookup scoped
+/// to the current user finds nothing, or a permission check fails.
+pub trait OrForbidden<T> {
+    fn or_forbidden(self, re: &impl Renderer) -> ServeResult<T>;
+}
+
+impl<T> OrForbidden<T> for Option<T> {
+    fn or_forbidden(self, re: &impl Renderer) -> ServeResult<T> {
+        match self {
+            Some(value) => Ok(value),
+            None => Err(ServeError::forbidden(re.clone())),
+        }
+    }
+}
+
+impl OrForbidden<()> for bool {
+    fn or_forbidden(self, re: &impl Renderer) -> ServeResult<()> {
+        match self {
+            true => Ok(()),
+            false => Err(ServeError::forbidden(re.clone())),
+        }
+    }
+}
@@ -1,17 +1,24 @@
 mod app;
+mod cookies;
+mod csrf;
 mod current;
 mod error;
 mod public;
 mod render;
+mod theme;
 
 pub use app::Application;
+pub use cookies::{CookiesExt, Verified};
+pub use csrf::csrf;
 pub use current::{
-    current, CurrentHooks, CurrentLanguage, CurrentLanguageState, CurrentSession,
-    CurrentSessionState, CurrentState, CurrentThemeState, CurrentUser, CurrentUserState,
+    current, CurrentCsrfState, CurrentFormatState, CurrentFragmentState, CurrentHooks,
+    CurrentLanguage, CurrentLanguageState, CurrentSession, CurrentSessionState, CurrentState,
+    CurrentThemeState, CurrentUser, CurrentUserState, Format,
 };
-pub use error::{OrNotFound, Re, ReFuture, ServeError, ServeResult};
+pub use error::{OrForbidden, OrNotFound, Re, ReFuture, ServeError, ServeResult};
 pub use public::router as public_router;
 pub use render::Renderer;
+pub use theme::switch_theme;
 
 pub async fn serve(router: axum::Router) -> anyhow::Result<()> {
     let addr = &crate::stuff::STUFF.web.addr;
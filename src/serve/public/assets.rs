@@ -0,0 +1,51 @@
+use crate::{fingerprint::AssetManifest, scratch, stuff::STUFF};
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tower_http::services::{ServeDir, ServeFile};
+
+pub(super) const IMMUTABLE: HeaderValue =
+    HeaderValue::from_static("public, max-age=31536000, immutable");
+pub(super) const NO_CACHE: HeaderValue = HeaderValue::from_static("no-cache");
+
+/// Serves the public dirs, where a fingerprinted URL is served with headers
+/// that let it be cached forever, unless its hash is out of date.
+pub async fn serve(request: Request) -> Response {
+    let fingerprinted = AssetManifest::get().resolve(request.uri().path());
+
+    let result = match fingerprinted {
+        Some(fingerprinted) => {
+            let cache_control = match fingerprinted.current {
+                true => IMMUTABLE,
+                false => NO_CACHE,
+            };
+
+            ServeFile::new(fingerprinted.file)
+                .try_call(request)
+                .await
+                .map(|mut response| {
+                    if response.status().is_success() {
+                        response
+                            .headers_mut()
+                            .insert(header::CACHE_CONTROL, cache_control);
+                    }
+                    response.into_response()
+                })
+        }
+        None => ServeDir::new(STUFF.public.dir.as_ref())
+            .fallback(ServeDir::new(scratch::public_dir()))
+            .try_call(request)
+            .await
+            .map(IntoResponse::into_response),
+    };
+
+    match result {
+        Ok(response) => response,
+        Err(error) => {
+            tracing::error!("error serving static file: {error}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
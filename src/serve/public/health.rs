@@ -0,0 +1,12 @@
+use crate::db::Db;
+use axum::http::StatusCode;
+
+pub async fn check(db: Db) -> (StatusCode, &'static str) {
+    match db.health().await {
+        Ok(()) => (StatusCode::OK, "ok"),
+        Err(error) => {
+            tracing::error!("health check failed: {error:?}");
+            (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+        }
+    }
+}
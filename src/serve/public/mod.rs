@@ -1,18 +1,24 @@
 use super::Application;
-use crate::{scratch, stuff::STUFF};
+use anyhow::Result;
 use axum::{routing::get, Router};
-use tower_http::services::ServeDir;
 
+mod assets;
+mod health;
 mod reload;
+mod theme;
 
-pub fn router<A: Application>(app: A) -> Router {
-    let public_dir = ServeDir::new(STUFF.public.dir.as_ref());
-    let scratch_dir = ServeDir::new(scratch::public_dir());
-    let service = public_dir.fallback(scratch_dir);
+/// The routes every application serves, once it has checked that it's
+/// ready to with [`Application::validate`].
+pub fn router<A: Application>(app: A) -> Result<Router> {
+    app.validate()?;
 
-    Router::new()
-        .fallback_service(service)
+    let router = Router::new()
+        .fallback(assets::serve)
+        .route("/__health__", get(health::check))
         .route("/__reload__", get(reload::js))
         .route("/__reload_sse__", get(reload::sse))
-        .with_state(app)
+        .route("/themes/:slug/*path", get(theme::asset))
+        .with_state(app);
+
+    Ok(router)
 }
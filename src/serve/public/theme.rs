@@ -0,0 +1,59 @@
+use super::assets::{IMMUTABLE, NO_CACHE};
+use crate::themes::{ThemeAsset, Themes};
+use axum::{
+    extract::{Path, Query, Request},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use tower_http::services::ServeFile;
+
+#[derive(Deserialize)]
+struct AssetVersion {
+    v: String,
+}
+
+/// Serves a file from a theme's `assets` dir, or from one of its ancestors.
+/// A URL from `asset_url` is versioned by the asset's hash, and can be cached
+/// forever while that hash is current.
+pub async fn asset(
+    themes: Themes,
+    Path((slug, path)): Path<(String, String)>,
+    request: Request,
+) -> Response {
+    let version = Query::<AssetVersion>::try_from_uri(request.uri()).ok();
+    let Some((asset, current)) = themes.get(&slug).map(|theme| {
+        let current =
+            version.is_some_and(|Query(version)| theme.is_current_asset(&path, &version.v));
+        (theme.find_asset(&path), current)
+    }) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let cache_control = match current {
+        true => IMMUTABLE,
+        false => NO_CACHE,
+    };
+
+    let mut response = match asset {
+        Some(ThemeAsset::File(file)) => match ServeFile::new(file).try_call(request).await {
+            Ok(response) => response.into_response(),
+            Err(error) => {
+                tracing::error!("error serving theme asset: {error}");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        },
+        Some(ThemeAsset::Bytes(bytes)) => {
+            let mime = mime_guess::from_path(&path).first_or_octet_stream();
+            ([(header::CONTENT_TYPE, mime.to_string())], bytes).into_response()
+        }
+        None => return StatusCode::NOT_FOUND.into_response(),
+    };
+
+    if response.status().is_success() {
+        response
+            .headers_mut()
+            .insert(header::CACHE_CONTROL, cache_control);
+    }
+    response
+}
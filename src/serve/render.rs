@@ -1,46 +1,109 @@
-use super::{Application, CurrentHooks, CurrentState, ServeError, ServeResult};
+use super::{Application, CurrentHooks, CurrentState, Re, ServeResult};
 use crate::themes::ThemeGuard;
 use anyhow::{Error, Result};
-use axum::response::{Html, IntoResponse, Response};
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
 use liquid::Object;
-use reqwest::StatusCode;
+
+/// The request headers that decide how [`Renderer::render`] responds, which
+/// are the `Accept` header and those asking for a fragment.
+const VARY: &str = "accept, plethora-fragment, turbo-frame, hx-request, hx-target, hx-boosted, \
+                    hx-history-restore-request";
 
 pub trait Renderer: Clone + Send + Sync + 'static {
     type App: Application;
     type Current: CurrentHooks;
 
     fn render(&self, template: &str, props: Object) -> ServeResult {
-        match self.try_render(template, props) {
-            Ok(response) => Ok(response),
-            Err(error) => Err(ServeError::new(self.clone(), error)),
-        }
+        self.try_render(template, props).re(self)
+    }
+
+    /// Renders a template like [`render`](Self::render), or responds with
+    /// the props themselves if the request prefers JSON. Only handlers whose
+    /// props are fit for any client to read should use this.
+    fn render_or_json(&self, template: &str, props: Object) -> ServeResult {
+        self.try_render_or_json(template, props).re(self)
+    }
+
+    /// Renders a template like [`render`](Self::render), but with another
+    /// status than 200. The whole page is rendered even if the request asks
+    /// for a fragment, which would be swapped in as if it had succeeded.
+    fn render_with_status(&self, status: StatusCode, template: &str, props: Object) -> ServeResult {
+        let response = self.try_render_page(template, props).re(self)?;
+        Ok((status, response).into_response())
     }
 
+    /// Renders a template without the layout, or only the named
+    /// `{% fragment %}` of it, for clients that swap parts of a page.
+    fn render_fragment(
+        &self,
+        template: &str,
+        fragment: Option<&str>,
+        props: Object,
+    ) -> ServeResult {
+        self.try_render_fragment(template, fragment, props).re(self)
+    }
+
+    /// Renders with the layout, unless the request asks for a fragment.
     fn try_render(&self, template: &str, props: Object) -> Result<Response> {
+        let current = self.current();
+        let response = if current.fragment.is_requested() {
+            self.try_render_fragment(template, current.fragment.name(), props)?
+        } else {
+            self.try_render_page(template, props)?
+        };
+
+        Ok(([(header::VARY, VARY)], response).into_response())
+    }
+
+    /// Renders a template with the layout, whatever the request asks for.
+    fn try_render_page(&self, template: &str, props: Object) -> Result<Response> {
         let theme = self.theme()?;
-        let base_title = self.app().base_page_title();
-        let html = theme.render(template, base_title, props, self.current())?;
+        let page = theme.render(template, self.app(), props, self.current())?;
 
-        Ok(Html(html).into_response())
+        Ok(page.into_response())
     }
 
-    fn try_render_error(&self, error: &Error) -> Result<Response> {
+    fn try_render_or_json(&self, template: &str, props: Object) -> Result<Response> {
+        if !self.current().format.is_json() {
+            return self.try_render(template, props);
+        }
+
+        Ok(([(header::VARY, VARY)], Json(props)).into_response())
+    }
+
+    fn try_render_fragment(
+        &self,
+        template: &str,
+        fragment: Option<&str>,
+        props: Object,
+    ) -> Result<Response> {
         let theme = self.theme()?;
-        let base_title = self.app().base_page_title();
-        let html = theme.render_error(error, base_title, self.current())?;
+        let page = theme.render_fragment(template, fragment, self.app(), props, self.current())?;
 
-        Ok((StatusCode::INTERNAL_SERVER_ERROR, Html(html)).into_response())
+        Ok(page.into_response())
+    }
+
+    fn try_render_error(&self, error: &Error) -> Result<Response> {
+        self.try_render_status(StatusCode::INTERNAL_SERVER_ERROR, Some(error))
     }
 
     fn try_render_not_found(&self) -> Result<Response> {
+        self.try_render_status(StatusCode::NOT_FOUND, None)
+    }
+
+    /// Renders the theme's page for an error status.
+    fn try_render_status(&self, status: StatusCode, error: Option<&Error>) -> Result<Response> {
         let theme = self.theme()?;
-        let base_title = self.app().base_page_title();
-        let html = theme.render_not_found(base_title, self.current())?;
+        let page = theme.render_status(status, error, self.app(), self.current())?;
 
-        Ok((StatusCode::NOT_FOUND, Html(html)).into_response())
+        Ok((status, page).into_response())
     }
 
-    fn theme(&self) -> Result<ThemeGuard> {
+    fn theme(&self) -> Result<ThemeGuard<'_>> {
         self.current().theme.resolve(self.app().themes())
     }
 
@@ -0,0 +1,43 @@
+use super::current::set_theme_cookie;
+use crate::themes::Themes;
+use axum::{
+    extract::Form,
+    http::{header, HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+};
+use serde::Deserialize;
+use tower_cookies::Cookies;
+
+#[derive(Debug, Deserialize)]
+pub struct SwitchTheme {
+    theme: String,
+}
+
+/// Persists the chosen theme in the `plethora-theme` cookie and redirects
+/// back to the page the form was submitted from. Mount it behind
+/// [`csrf`](super::csrf), as `POST /__theme__` in the basic example.
+pub async fn switch_theme(
+    themes: Themes,
+    cookies: Cookies,
+    headers: HeaderMap,
+    Form(form): Form<SwitchTheme>,
+) -> Response {
+    if themes.get(&form.theme).is_none() {
+        return (StatusCode::BAD_REQUEST, "unknown theme").into_response();
+    }
+
+    set_theme_cookie(&cookies, &form.theme);
+    Redirect::to(&back(&headers)).into_response()
+}
+
+/// The path of the referring page, dropping its origin so that the redirect
+/// can never leave the site.
+fn back(headers: &HeaderMap) -> String {
+    headers
+        .get(header::REFERER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<Uri>().ok())
+        .and_then(|uri| uri.path_and_query().map(|pq| pq.to_string()))
+        .filter(|path| path.starts_with('/') && !path.starts_with("//"))
+        .unwrap_or_else(|| "/".to_string())
+}
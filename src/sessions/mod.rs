@@ -0,0 +1,175 @@
+use self::store::Store;
+use crate::{
+    db::{Db, FromRow, Id},
+    serve::CookiesExt,
+    stuff::{StuffSameSite, StuffSessionStore, STUFF},
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tower_cookies::{
+    cookie::{time::Duration, SameSite},
+    Cookie, Cookies,
+};
+
+mod store;
+
+pub const COOKIE: &str = "plethora-session";
+
+/// The server-side record of a session, resolved from the session cookie
+/// before being handed to [`CurrentHooks::session`](crate::serve::CurrentHooks::session).
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct StoredSession {
+    pub id: Id,
+    pub user_id: Id,
+    pub expires_at: i64,
+}
+
+impl StoredSession {
+    fn is_expired(&self, now: i64) -> bool {
+        self.expires_at <= now
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sessions {
+    store: Store,
+}
+
+impl Sessions {
+    pub async fn new(db: Db) -> Result<Self> {
+        let store = match STUFF.session.store {
+            StuffSessionStore::Db => Store::db(db).await?,
+            StuffSessionStore::Memory => Store::memory(),
+        };
+
+        Ok(Self { store })
+    }
+
+    /// Creates a session for `user_id` and sets the session cookie.
+    pub async fn login(&self, cookies: &Cookies, user_id: Id) -> Result<StoredSession> {
+        if let Some(id) = cookie_id(cookies) {
+            self.store.remove(id).await?;
+        }
+
+        let session = StoredSession {
+            id: Id::new_v4(),
+            user_id,
+            expires_at: now() + max_age(),
+        };
+
+        self.store.insert(&session).await?;
+        set_cookie(cookies, &session);
+
+        Ok(session)
+    }
+
+    /// Revokes the current session, if any, and removes the session cookie.
+    pub async fn logout(&self, cookies: &Cookies) -> Result<()> {
+        if let Some(id) = cookie_id(cookies) {
+            self.store.remove(id).await?;
+        }
+
+        cookies.remove(Cookie::build(COOKIE).path("/").into());
+        Ok(())
+    }
+
+    /// Replaces the current session with a fresh id for the same user, as
+    /// should be done whenever the user's privileges change.
+    pub async fn rotate(&self, cookies: &Cookies) -> Result<Option<StoredSession>> {
+        let Some(session) = self.resume(cookies).await? else {
+            return Ok(None);
+        };
+
+        self.login(cookies, session.user_id).await.map(Some)
+    }
+
+    pub async fn revoke(&self, id: Id) -> Result<()> {
+        self.store.remove(id).await
+    }
+
+    /// Revokes every session of `user_id`, logging them out everywhere.
+    pub async fn revoke_user(&self, user_id: Id) -> Result<()> {
+        self.store.remove_user(user_id).await
+    }
+
+    pub async fn purge_expired(&self) -> Result<()> {
+        self.store.remove_expired(now()).await
+    }
+
+    /// Resolves the session cookie to an unexpired session. With sliding
+    /// expiry, sessions past half their lifetime are extended.
+    pub(crate) async fn resume(&self, cookies: &Cookies) -> Result<Option<StoredSession>> {
+        let Some((id, stale)) = read_cookie(cookies) else {
+            return Ok(None);
+        };
+
+        let now = now();
+        let Some(mut session) = self.store.get(id).await? else {
+            cookies.remove(Cookie::build(COOKIE).path("/").into());
+            return Ok(None);
+        };
+
+        if session.is_expired(now) {
+            self.store.remove(id).await?;
+            cookies.remove(Cookie::build(COOKIE).path("/").into());
+            return Ok(None);
+        }
+
+        if STUFF.session.sliding && session.expires_at - now < max_age() / 2 {
+            session.expires_at = now + max_age();
+            self.store.touch(&session).await?;
+            set_cookie(cookies, &session);
+        } else if stale {
+            set_cookie(cookies, &session);
+        }
+
+        Ok(Some(session))
+    }
+}
+
+/// The id in the session cookie, which may be expired or revoked.
+pub(crate) fn cookie_id(cookies: &Cookies) -> Option<Id> {
+    read_cookie(cookies).map(|(id, _)| id)
+}
+
+/// The id in the session cookie, and whether the cookie was written with an
+/// old secret.
+fn read_cookie(cookies: &Cookies) -> Option<(Id, bool)> {
+    let verified = cookies.get_private(COOKIE)?;
+    match Id::parse_str(verified.cookie.value()) {
+        Ok(id) => Some((id, verified.stale)),
+        Err(_) => {
+            tracing::debug!(value = %verified.cookie.value(), "malformed session cookie");
+            None
+        }
+    }
+}
+
+fn set_cookie(cookies: &Cookies, session: &StoredSession) {
+    let same_site = match STUFF.session.same_site {
+        StuffSameSite::Strict => SameSite::Strict,
+        StuffSameSite::Lax => SameSite::Lax,
+        StuffSameSite::None => SameSite::None,
+    };
+
+    let cookie = Cookie::build((COOKIE, session.id.to_string()))
+        .path("/")
+        .http_only(true)
+        .secure(STUFF.session.secure)
+        .same_site(same_site)
+        .max_age(Duration::seconds(max_age()));
+
+    cookies.add_private(cookie.into());
+}
+
+fn max_age() -> i64 {
+    STUFF.session.max_age as i64
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,133 @@
+use super::StoredSession;
+use crate::db::{query, query_as, Db, Id};
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use std::sync::Arc;
+
+const TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS _plethora_sessions (
+    id BLOB PRIMARY KEY,
+    user_id BLOB NOT NULL,
+    expires_at INTEGER NOT NULL
+)
+"#;
+
+const INDEX: &str =
+    "CREATE INDEX IF NOT EXISTS _plethora_sessions_user_id ON _plethora_sessions (user_id)";
+
+#[derive(Debug, Clone)]
+pub enum Store {
+    Db(Db),
+    Memory(Arc<DashMap<Id, StoredSession>>),
+}
+
+impl Store {
+    /// Creates the sessions table if it doesn't exist yet, so apps don't need
+    /// a migration for it.
+    pub async fn db(db: Db) -> Result<Self> {
+        for sql in [TABLE, INDEX] {
+            query(sql)
+                .execute(db.pool())
+                .await
+                .context("failed to create _plethora_sessions")?;
+        }
+        Ok(Self::Db(db))
+    }
+
+    pub fn memory() -> Self {
+        Self::Memory(Arc::new(DashMap::new()))
+    }
+
+    pub async fn get(&self, id: Id) -> Result<Option<StoredSession>> {
+        match self {
+            Self::Db(db) => {
+                let session = query_as("SELECT * FROM _plethora_sessions WHERE id = ?")
+                    .bind(id)
+                    .fetch_optional(db.pool())
+                    .await?;
+                Ok(session)
+            }
+            Self::Memory(map) => Ok(map.get(&id).map(|s| s.clone())),
+        }
+    }
+
+    pub async fn insert(&self, session: &StoredSession) -> Result<()> {
+        match self {
+            Self::Db(db) => {
+                query("INSERT INTO _plethora_sessions (id, user_id, expires_at) VALUES (?, ?, ?)")
+                    .bind(session.id)
+                    .bind(session.user_id)
+                    .bind(session.expires_at)
+                    .execute(db.pool())
+                    .await?;
+            }
+            Self::Memory(map) => {
+                map.insert(session.id, session.clone());
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn touch(&self, session: &StoredSession) -> Result<()> {
+        match self {
+            Self::Db(db) => {
+                query("UPDATE _plethora_sessions SET expires_at = ? WHERE id = ?")
+                    .bind(session.expires_at)
+                    .bind(session.id)
+                    .execute(db.pool())
+                    .await?;
+            }
+            Self::Memory(map) => {
+                if let Some(mut stored) = map.get_mut(&session.id) {
+                    stored.expires_at = session.expires_at;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn remove(&self, id: Id) -> Result<()> {
+        match self {
+            Self::Db(db) => {
+                query("DELETE FROM _plethora_sessions WHERE id = ?")
+                    .bind(id)
+                    .execute(db.pool())
+                    .await?;
+            }
+            Self::Memory(map) => {
+                map.remove(&id);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn remove_user(&self, user_id: Id) -> Result<()> {
+        match self {
+            Self::Db(db) => {
+                query("DELETE FROM _plethora_sessions WHERE user_id = ?")
+                    .bind(user_id)
+                    .execute(db.pool())
+                    .await?;
+            }
+            Self::Memory(map) => {
+                map.retain(|_, s| s.user_id != user_id);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn remove_expired(&self, now: i64) -> Result<()> {
+        match self {
+            Self::Db(db) => {
+                query("DELETE FROM _plethora_sessions WHERE expires_at <= ?")
+                    .bind(now)
+                    .execute(db.pool())
+                    .await?;
+            }
+            Self::Memory(map) => {
+                map.retain(|_, s| !s.is_expired(now));
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,12 @@
 use super::{Stuff, STUFF};
-use anyhow::{ensure, Result};
+use anyhow::{bail, ensure, Result};
 use camino::Utf8PathBuf;
-use config::{builder::DefaultState, Config, ConfigBuilder, File, FileFormat};
+use config::{builder::DefaultState, Config, ConfigBuilder, ConfigError, File, FileFormat};
+use serde::de::DeserializeOwned;
 use std::env;
 
+const MIN_SECRET_LEN: usize = 32;
+
 const DEFAULT: &str = include_str!("./default.toml");
 
 pub fn builder() -> StuffBuilder {
@@ -46,6 +49,7 @@ impl StuffBuilder {
     pub fn init(self) -> Result<StuffGuard> {
         let config = self.config.build()?;
         let stuff = make(config)?;
+        validate(&stuff)?;
 
         let guard = super::trace::init(&stuff)?;
         let guard = StuffGuard { _trace: guard };
@@ -65,12 +69,20 @@ pub struct StuffGuard {
 fn make(config: Config) -> Result<Stuff> {
     use super::*;
     Ok(Stuff {
+        cookies: StuffCookies {
+            secret: optional(&config, "cookies.secret")?,
+            old_secrets: config.get("cookies.old_secrets")?,
+        },
         db: StuffDb {
             url: config.get("db.url")?,
+            max_connections: config.get("db.max_connections")?,
+            migrations: config.get("db.migrations")?,
         },
         lang: StuffLang {
             #[cfg(feature = "langdir")]
             dir: config.get("lang.dir")?,
+            default: config.get("lang.default")?,
+            supported: config.get("lang.supported")?,
         },
         log: StuffLog {
             #[cfg(feature = "packaged")]
@@ -92,18 +104,61 @@ fn make(config: Config) -> Result<Stuff> {
             glob: config.get("scripts.glob")?,
             autoload: config.get("scripts.autoload")?,
         },
+        session: StuffSession {
+            store: config.get("session.store")?,
+            max_age: config.get("session.max_age")?,
+            sliding: config.get("session.sliding")?,
+            secure: config.get("session.secure")?,
+            same_site: config.get("session.same_site")?,
+        },
         setup: StuffSetup {
             theme: config.get("setup.theme")?,
         },
         templates: StuffTemplates {
             boundary_comments: config.get("templates.boundary_comments")?,
+            cache_size: config.get("templates.cache_size")?,
         },
         themes: StuffThemes {
             dir: config.get("themes.dir")?,
             manifest_path: config.get("themes.manifest_path")?,
+            settings: optional(&config, "themes.settings")?.unwrap_or_default(),
         },
         web: StuffWeb {
             addr: config.get("web.addr")?,
         },
     })
 }
+
+fn optional<T: DeserializeOwned>(config: &Config, key: &str) -> Result<Option<T>> {
+    match config.get(key) {
+        Ok(value) => Ok(Some(value)),
+        Err(ConfigError::NotFound(_)) => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn validate(stuff: &Stuff) -> Result<()> {
+    let secrets = stuff
+        .cookies
+        .secret
+        .iter()
+        .chain(&*stuff.cookies.old_secrets);
+    for secret in secrets {
+        ensure!(
+            secret.len() >= MIN_SECRET_LEN,
+            "cookie secrets must be at least {MIN_SECRET_LEN} bytes"
+        );
+    }
+
+    if stuff.cookies.secret.is_none() && !stuff.reload {
+        bail!("cookies.secret is required unless reloading");
+    }
+
+    ensure!(
+        stuff.lang.supported.contains(&stuff.lang.default),
+        "lang.default {} is not in lang.supported",
+        stuff.lang.default
+    );
+
+    Ok(())
+}
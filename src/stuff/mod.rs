@@ -1,6 +1,7 @@
 use camino::Utf8Path;
 use kstring::KString;
-use std::{ops::Deref, sync::OnceLock};
+use serde::Deserialize;
+use std::{collections::HashMap, fmt, ops::Deref, sync::OnceLock};
 
 mod builder;
 mod trace;
@@ -27,6 +28,7 @@ impl Deref for StuffLock {
 
 #[derive(Debug)]
 pub struct Stuff {
+    pub cookies: StuffCookies,
     pub db: StuffDb,
     pub lang: StuffLang,
     pub log: StuffLog,
@@ -35,21 +37,42 @@ pub struct Stuff {
     pub root: Box<Utf8Path>,
     pub scratch: StuffScratch,
     pub scripts: StuffScripts,
+    pub session: StuffSession,
     pub setup: StuffSetup,
     pub templates: StuffTemplates,
     pub themes: StuffThemes,
     pub web: StuffWeb,
 }
 
+pub struct StuffCookies {
+    pub secret: Option<Box<str>>,
+    pub old_secrets: Box<[Box<str>]>,
+}
+
+impl fmt::Debug for StuffCookies {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = format_args!("[redacted]");
+
+        f.debug_struct("StuffCookies")
+            .field("secret", &self.secret.as_ref().map(|_| redacted))
+            .field("old_secrets", &vec![redacted; self.old_secrets.len()])
+            .finish()
+    }
+}
+
 #[derive(Debug)]
 pub struct StuffDb {
     pub url: Box<str>,
+    pub max_connections: u32,
+    pub migrations: Box<Utf8Path>,
 }
 
 #[derive(Debug)]
 pub struct StuffLang {
     #[cfg(feature = "langdir")]
     pub dir: Box<Utf8Path>,
+    pub default: KString,
+    pub supported: Box<[KString]>,
 }
 
 #[derive(Debug)]
@@ -76,6 +99,30 @@ pub struct StuffScripts {
     pub autoload: Box<[KString]>,
 }
 
+#[derive(Debug)]
+pub struct StuffSession {
+    pub store: StuffSessionStore,
+    pub max_age: u64,
+    pub sliding: bool,
+    pub secure: bool,
+    pub same_site: StuffSameSite,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StuffSessionStore {
+    Db,
+    Memory,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StuffSameSite {
+    Strict,
+    Lax,
+    None,
+}
+
 #[derive(Debug)]
 pub struct StuffSetup {
     pub theme: Box<str>,
@@ -84,12 +131,16 @@ pub struct StuffSetup {
 #[derive(Debug)]
 pub struct StuffTemplates {
     pub boundary_comments: bool,
+    /// The most bytes of output that `{% cache %}` blocks keep.
+    pub cache_size: usize,
 }
 
 #[derive(Debug)]
 pub struct StuffThemes {
     pub dir: Box<Utf8Path>,
     pub manifest_path: Box<Utf8Path>,
+    /// Overrides for the `[settings]` of each theme, by slug.
+    pub settings: HashMap<KString, toml::Table>,
 }
 
 #[derive(Debug)]
@@ -11,11 +11,14 @@ pub struct Process {
 
 impl Process {
     pub async fn new(binary: &Utf8Path, theme: &Theme) -> Result<Self> {
-        let mut command = Command::new(binary.as_str());
-
-        let input = theme.tailwind_input_path();
+        let (Some(input), Some(config)) =
+            (theme.tailwind_input_path(), theme.tailwind_config_path())
+        else {
+            // The theme has no stylesheet to compile.
+            return Ok(Self { _child: None });
+        };
         let output = theme.tailwind_output_path();
-        let config = theme.tailwind_config_path();
+        let mut command = Command::new(binary.as_str());
 
         command
             .kill_on_drop(true)
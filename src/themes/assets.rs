@@ -0,0 +1,151 @@
+use super::Theme;
+use crate::{fingerprint::content_hash, helper::fs::walk_dir_async};
+use ahash::AHashMap;
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use futures::StreamExt;
+use kstring::KString;
+use std::{pin::pin, sync::Arc};
+
+/// The dir within a theme that holds its static assets.
+pub const ASSETS_DIR: &str = "assets";
+
+/// Where the assets of one theme in a lineage are read from.
+#[derive(Debug, Clone)]
+pub(super) enum AssetDir {
+    Files(Utf8PathBuf),
+    #[cfg(feature = "baked-themes")]
+    Baked(include_dir::Dir<'static>),
+}
+
+/// A static asset of a theme, found in the theme or one of its ancestors.
+#[derive(Debug, Clone)]
+pub enum ThemeAsset {
+    File(Utf8PathBuf),
+    Bytes(&'static [u8]),
+}
+
+/// The content hash of each asset a theme serves, keyed by its path within
+/// the `assets` dir, which versions the asset's URL.
+#[derive(Debug, Clone, Default)]
+pub struct AssetHashes(Arc<AHashMap<KString, KString>>);
+
+impl AssetHashes {
+    /// Hashes the assets of a lineage, nearest theme first, so that each path
+    /// gets the hash of the file [`Theme::find_asset`] serves for it.
+    pub(super) async fn new(dirs: &[AssetDir]) -> Self {
+        let mut hashes = AHashMap::new();
+
+        for dir in dirs {
+            match dir {
+                AssetDir::Files(dir) => {
+                    let mut files = pin!(walk_dir_async(dir));
+
+                    while let Some(file) = files.next().await {
+                        let Ok(path) = file.strip_prefix(dir) else {
+                            continue;
+                        };
+                        let path = KString::from_ref(path.as_str());
+                        if hashes.contains_key(&path) {
+                            continue;
+                        }
+
+                        match tokio::fs::read(&file).await {
+                            Ok(bytes) => {
+                                hashes.insert(path, content_hash(&bytes));
+                            }
+                            Err(error) => {
+                                tracing::warn!("error hashing theme asset {file}: {error}")
+                            }
+                        }
+                    }
+                }
+                #[cfg(feature = "baked-themes")]
+                AssetDir::Baked(dir) => {
+                    let mut stack = vec![dir];
+
+                    while let Some(next) = stack.pop() {
+                        for entry in next.entries() {
+                            match entry {
+                                include_dir::DirEntry::Dir(d) => stack.push(d),
+                                include_dir::DirEntry::File(f) => {
+                                    let Some(path) = f
+                                        .path()
+                                        .strip_prefix(dir.path())
+                                        .ok()
+                                        .and_then(Utf8Path::from_path)
+                                    else {
+                                        continue;
+                                    };
+
+                                    hashes
+                                        .entry(KString::from_ref(path.as_str()))
+                                        .or_insert_with(|| content_hash(f.contents()));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Self(Arc::new(hashes))
+    }
+
+    fn get(&self, path: &str) -> Option<&KString> {
+        self.0.get(path.trim_start_matches('/'))
+    }
+}
+
+impl Theme {
+    /// The URL of an asset of this theme, versioned by the hash of its
+    /// content.
+    pub fn asset_url(&self, path: &str) -> String {
+        url(&self.slug, &self.asset_hashes, path)
+    }
+
+    /// Whether `version` is the hash of the asset's current content, so that
+    /// the response can be cached forever.
+    pub fn is_current_asset(&self, path: &str, version: &str) -> bool {
+        self.asset_hashes
+            .get(path)
+            .is_some_and(|hash| hash == version)
+    }
+
+    /// Finds the asset at `path`, relative to the theme's `assets` dir, in
+    /// this theme or else in its nearest ancestor that has it.
+    pub fn find_asset(&self, path: &str) -> Option<ThemeAsset> {
+        let path = Utf8Path::new(path);
+
+        if !is_contained(path) {
+            return None;
+        }
+
+        self.assets.iter().find_map(|dir| match dir {
+            AssetDir::Files(dir) => {
+                let path = dir.join(path);
+                path.is_file().then_some(ThemeAsset::File(path))
+            }
+            #[cfg(feature = "baked-themes")]
+            AssetDir::Baked(dir) => dir
+                .get_file(dir.path().join(path))
+                .map(|file| ThemeAsset::Bytes(file.contents())),
+        })
+    }
+}
+
+pub(super) fn url(slug: &str, hashes: &AssetHashes, path: &str) -> String {
+    let path = path.trim_start_matches('/');
+
+    match hashes.get(path) {
+        Some(hash) => format!("/themes/{slug}/{path}?v={hash}"),
+        None => format!("/themes/{slug}/{path}"),
+    }
+}
+
+/// Whether `path` is relative and stays within the dir it's joined to.
+pub(super) fn is_contained(path: &Utf8Path) -> bool {
+    path.is_relative()
+        && path
+            .components()
+            .all(|c| matches!(c, Utf8Component::Normal(_)))
+}
@@ -1,5 +1,8 @@
-use super::{ingest, Themes};
-use crate::styles::Styles;
+use super::{
+    ingest::{self, Ingest},
+    FragmentCache, Themes,
+};
+use crate::{stuff::STUFF, styles::Styles};
 use anyhow::Result;
 use dashmap::DashMap;
 use std::sync::Arc;
@@ -30,8 +33,17 @@ impl ThemesBuilder {
 
     pub async fn build(self) -> Result<Themes> {
         let map = Arc::new(DashMap::new());
+        let listing = Arc::default();
+        let fallback = Arc::new(ingest::Builtin::ingest(()).await?);
         let styles = self.styles;
-        let themes = Themes { map, styles };
+        let themes = Themes {
+            map,
+            listing,
+            fallback,
+            overrides: Default::default(),
+            fragments: FragmentCache::new(STUFF.templates.cache_size),
+            styles,
+        };
 
         #[cfg(feature = "baked-themes")]
         if let Some(baked) = self.baked {
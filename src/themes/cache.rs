@@ -0,0 +1,224 @@
+//! Rendered output of `{% cache %}` blocks, shared by all themes and bounded
+//! in size, evicting the least recently used output first.
+
+use super::{templates::Effect, Themes};
+use kstring::KString;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone)]
+pub struct FragmentCache(Arc<Mutex<Inner>>);
+
+/// Where a block was rendered, along with its evaluated key. The language is
+/// part of it so translated output is never served in another language.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FragmentKey {
+    pub theme: KString,
+    pub template: KString,
+    pub language: KString,
+    pub key: KString,
+}
+
+/// The output of a `{% cache %}` block, with what its tags left for the
+/// layout.
+#[derive(Debug)]
+pub struct CachedFragment {
+    pub html: String,
+    pub effects: Vec<Effect>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    entries: HashMap<FragmentKey, Entry>,
+    /// Each key by when it was last used, oldest first.
+    used: BTreeMap<u64, FragmentKey>,
+    tick: u64,
+    size: usize,
+    capacity: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    fragment: Arc<CachedFragment>,
+    expires: Option<Instant>,
+    used: u64,
+}
+
+impl Themes {
+    /// Drops the cached output of every `{% cache %}` block whose key starts
+    /// with `prefix`, in all themes.
+    pub fn invalidate_cache(&self, prefix: &str) {
+        self.fragments.retain(|key| !key.key.starts_with(prefix));
+    }
+}
+
+impl FragmentCache {
+    /// A cache that holds up to `capacity` bytes of output.
+    pub fn new(capacity: usize) -> Self {
+        Self(Arc::new(Mutex::new(Inner {
+            entries: HashMap::new(),
+            used: BTreeMap::new(),
+            tick: 0,
+            size: 0,
+            capacity,
+        })))
+    }
+
+    pub fn get(&self, key: &FragmentKey) -> Option<Arc<CachedFragment>> {
+        let mut inner = self.lock();
+        let expired = inner
+            .entries
+            .get(key)?
+            .expires
+            .is_some_and(|expires| expires <= Instant::now());
+
+        if expired {
+            inner.remove(key);
+            return None;
+        }
+
+        inner.tick += 1;
+        let tick = inner.tick;
+        let entry = inner.entries.get_mut(key)?;
+        let used = std::mem::replace(&mut entry.used, tick);
+        let fragment = entry.fragment.clone();
+
+        let key = inner.used.remove(&used)?;
+        inner.used.insert(tick, key);
+        Some(fragment)
+    }
+
+    pub fn insert(&self, key: FragmentKey, fragment: CachedFragment, ttl: Option<Duration>) {
+        let mut inner = self.lock();
+        let size = entry_size(&key, &fragment);
+
+        inner.remove(&key);
+        if size > inner.capacity {
+            return;
+        }
+
+        while inner.size + size > inner.capacity {
+            let Some((_, oldest)) = inner.used.pop_first() else {
+                break;
+            };
+            inner.remove(&oldest);
+        }
+
+        inner.tick += 1;
+        let entry = Entry {
+            fragment: Arc::new(fragment),
+            expires: ttl.map(|ttl| Instant::now() + ttl),
+            used: inner.tick,
+        };
+
+        inner.size += size;
+        inner.used.insert(entry.used, key.clone());
+        inner.entries.insert(key, entry);
+    }
+
+    /// Drops the output cached while rendering a theme.
+    pub(super) fn flush(&self, theme: &str) {
+        self.retain(|key| key.theme != theme);
+    }
+
+    fn retain(&self, keep: impl Fn(&FragmentKey) -> bool) {
+        let mut inner = self.lock();
+        let removed = inner
+            .entries
+            .keys()
+            .filter(|key| !keep(key))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for key in removed {
+            inner.remove(&key);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Inner {
+    fn remove(&mut self, key: &FragmentKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.used.remove(&entry.used);
+            self.size -= entry_size(key, &entry.fragment);
+        }
+    }
+}
+
+fn entry_size(key: &FragmentKey, fragment: &CachedFragment) -> usize {
+    let effects = fragment.effects.iter().map(Effect::len).sum::<usize>();
+    let key = key.theme.len() + key.template.len() + key.language.len() + key.key.len();
+
+    key + fragment.html.len() + effects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn html(html: &str) -> CachedFragment {
+        CachedFragment {
+            html: html.to_string(),
+            effects: Vec::new(),
+        }
+    }
+
+    fn key(key: &str) -> FragmentKey {
+        FragmentKey {
+            theme: "default".into(),
+            template: "index".into(),
+            language: "en".into(),
+            key: KString::from_ref(key),
+        }
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let size = entry_size(&key("a"), &html("0123456789"));
+        let cache = FragmentCache::new(size * 2);
+
+        cache.insert(key("a"), html("0123456789"), None);
+        cache.insert(key("b"), html("0123456789"), None);
+        assert!(cache.get(&key("a")).is_some());
+
+        cache.insert(key("c"), html("0123456789"), None);
+        assert!(cache.get(&key("a")).is_some());
+        assert!(cache.get(&key("b")).is_none());
+        assert!(cache.get(&key("c")).is_some());
+
+        cache.insert(key("d"), html(&"x".repeat(size * 2)), None);
+        assert!(cache.get(&key("d")).is_none());
+        assert!(cache.get(&key("a")).is_some());
+    }
+
+    #[test]
+    fn expires_and_invalidates() {
+        let cache = FragmentCache::new(1024);
+
+        cache.insert(key("sidebar:1"), html("one"), Some(Duration::ZERO));
+        assert!(cache.get(&key("sidebar:1")).is_none());
+
+        cache.insert(key("sidebar:1"), html("one"), None);
+        cache.insert(key("sidebar:2"), html("two"), None);
+        cache.insert(key("footer"), html("three"), None);
+        cache.retain(|key| !key.key.starts_with("sidebar:"));
+
+        assert!(cache.get(&key("sidebar:1")).is_none());
+        assert!(cache.get(&key("sidebar:2")).is_none());
+        let footer = cache.get(&key("footer")).unwrap();
+        assert_eq!(footer.html, "three");
+
+        cache.flush("default");
+        assert!(cache.get(&key("footer")).is_none());
+        assert_eq!(cache.lock().size, 0);
+    }
+}
@@ -1,5 +1,5 @@
-use super::{IngestImpl, IngestManyImpl};
-use crate::stuff::STUFF;
+use super::{AssetDir, IngestImpl, IngestManyImpl};
+use crate::{stuff::STUFF, themes::ASSETS_DIR};
 use anyhow::{Context, Result};
 use camino::{Utf8Path, Utf8PathBuf};
 use futures::{stream, Stream, StreamExt};
@@ -11,17 +11,18 @@ type DirEntry = include_dir::DirEntry<'static>;
 type ThemeDir = &'static Path;
 
 pub struct Baked {
+    root: Dir,
     dir: Dir,
 }
 
 #[allow(private_interfaces)]
 impl IngestImpl for Baked {
-    type Data = Dir;
+    type Data = (Dir, Dir);
 
     const KIND: &'static str = "baked";
 
-    fn new(dir: Dir) -> Self {
-        Self { dir }
+    fn new((root, dir): (Dir, Dir)) -> Self {
+        Self { root, dir }
     }
 
     fn slug(&self) -> Result<KString> {
@@ -43,7 +44,25 @@ impl IngestImpl for Baked {
         }
     }
 
-    fn entries(self) -> impl Stream<Item = super::File> {
+    fn assets(&self) -> Option<AssetDir> {
+        let dir = self.dir.get_dir(self.dir.path().join(ASSETS_DIR))?;
+        Some(AssetDir::Baked(dir.clone()))
+    }
+
+    fn has_file(&self, path: &str) -> bool {
+        self.dir.get_file(self.dir.path().join(path)).is_some()
+    }
+
+    fn sibling(&self, slug: &str) -> Result<Self> {
+        let dir = self
+            .root
+            .get_dir(slug)
+            .with_context(|| format!("parent theme {slug} not found"))?;
+
+        Ok(Self::new((self.root.clone(), dir.clone())))
+    }
+
+    fn entries(&self) -> impl Stream<Item = super::File> {
         fn read(dir: Dir, theme_dir: ThemeDir, stack: &mut Vec<Dir>) -> Vec<super::File> {
             dir.entries()
                 .iter()
@@ -69,7 +88,7 @@ impl IngestImpl for Baked {
         }
 
         let theme_dir = self.dir.path();
-        let stack = vec![self.dir];
+        let stack = vec![self.dir.clone()];
 
         stream::unfold(stack, |mut stack| async {
             let dir = stack.pop()?;
@@ -92,7 +111,8 @@ impl IngestManyImpl for Baked {
         stream::iter(dir.dirs().filter_map(f))
     }
 
-    async fn data_stream(dir: Dir) -> impl Stream<Item = Self::Data> {
-        stream::iter(dir.dirs().cloned())
+    async fn data_stream(root: Dir) -> impl Stream<Item = Self::Data> {
+        let dirs = root.dirs().cloned().collect::<Vec<_>>();
+        stream::iter(dirs.into_iter().map(move |dir| (root.clone(), dir)))
     }
 }
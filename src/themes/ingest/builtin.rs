@@ -0,0 +1,60 @@
+use super::{AssetDir, IngestImpl};
+use anyhow::Result;
+use futures::{stream, Stream};
+use kstring::KString;
+
+/// The slug of the built-in theme, which is used when neither the requested
+/// nor the default theme is loaded.
+pub const FALLBACK_SLUG: &str = "__fallback__";
+
+const MANIFEST: &str = include_str!("builtin/mod.toml");
+const FILES: &[(&str, &str)] = &[
+    (
+        "_layouts/layout.liquid",
+        include_str!("builtin/_layouts/layout.liquid"),
+    ),
+    (
+        "_errors/error.liquid",
+        include_str!("builtin/_errors/error.liquid"),
+    ),
+    (
+        "_errors/not_found.liquid",
+        include_str!("builtin/_errors/not_found.liquid"),
+    ),
+];
+
+pub struct Builtin;
+
+#[allow(private_interfaces)]
+impl IngestImpl for Builtin {
+    type Data = ();
+
+    const KIND: &'static str = "builtin";
+
+    fn new((): ()) -> Self {
+        Self
+    }
+
+    fn slug(&self) -> Result<KString> {
+        Ok(KString::from_static(FALLBACK_SLUG))
+    }
+
+    async fn manifest(&self) -> Result<KString> {
+        Ok(KString::from_static(MANIFEST))
+    }
+
+    fn entries(&self) -> impl Stream<Item = super::File> {
+        stream::iter(FILES.iter().map(|(path, text)| super::File {
+            path: path.to_string(),
+            text: text.to_string(),
+        }))
+    }
+
+    fn assets(&self) -> Option<AssetDir> {
+        None
+    }
+
+    fn has_file(&self, path: &str) -> bool {
+        FILES.iter().any(|(file, _)| *file == path)
+    }
+}
@@ -1,9 +1,10 @@
-use super::{IngestImpl, IngestManyImpl};
+use super::{AssetDir, IngestImpl, IngestManyImpl};
 use crate::{
     helper::fs::{read_dir_async, walk_dir_async},
     stuff::STUFF,
+    themes::ASSETS_DIR,
 };
-use anyhow::Result;
+use anyhow::{ensure, Context, Result};
 use camino::{Utf8Path, Utf8PathBuf};
 use futures::{Stream, StreamExt};
 use kstring::KString;
@@ -43,7 +44,37 @@ impl IngestImpl for Files {
         }
     }
 
-    fn entries(self) -> impl Stream<Item = super::File> {
+    fn assets(&self) -> Option<AssetDir> {
+        let dir = self.dir.join(ASSETS_DIR);
+        dir.is_dir().then_some(AssetDir::Files(dir))
+    }
+
+    fn has_file(&self, path: &str) -> bool {
+        self.dir.join(path).is_file()
+    }
+
+    fn sibling(&self, slug: &str) -> Result<Self> {
+        ensure!(
+            !slug.is_empty() && !slug.contains(['/', '\\']) && !slug.contains(".."),
+            "invalid parent theme {slug:?}"
+        );
+
+        let dir = self
+            .dir
+            .parent()
+            .context("theme has no parent dir")?
+            .join(slug);
+        ensure!(
+            dir.parent() == Some(&STUFF.themes.dir),
+            "parent theme {slug} is not in {}",
+            STUFF.themes.dir
+        );
+        ensure!(dir.is_dir(), "parent theme {slug} not found");
+
+        Ok(Self::new(dir))
+    }
+
+    fn entries(&self) -> impl Stream<Item = super::File> {
         // Can't use a regular .filter because that takes a reference
         // and thus has lifetime issues in async streams.
         async fn filter_ext(path: Utf8PathBuf) -> Option<Utf8PathBuf> {
@@ -59,7 +90,7 @@ impl IngestImpl for Files {
 
         walk_dir_async(&self.dir)
             .filter_map(filter_ext)
-            .map(move |p| read(p, self.dir.clone()))
+            .map(|p| read(p, self.dir.clone()))
             .buffer_unordered(CONCURRENCY)
             .filter_map(|r| async move { r.ok() })
     }
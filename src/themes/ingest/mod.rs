@@ -1,19 +1,33 @@
-use super::Theme;
-use crate::themes::templates::{Parser, Templates};
-use anyhow::{Context, Result};
+use self::validate::Layer;
+use super::{
+    assets::{AssetDir, AssetHashes},
+    Theme, ThemeManifest,
+};
+use crate::{
+    stuff::STUFF,
+    themes::templates::{desugar, Parser, Templates},
+};
+use anyhow::{bail, Context, Result};
 use camino::Utf8PathBuf;
 use futures::{Stream, StreamExt};
 use kstring::KString;
-use liquid::partials::{EagerCompiler, InMemorySource};
+use liquid::partials::InMemorySource;
+use serde::Deserialize;
 use std::{future::Future, pin::pin};
+use toml::Table;
 
 #[cfg(feature = "baked-themes")]
 mod baked;
+mod builtin;
 mod files;
+mod report;
+mod validate;
 
 #[cfg(feature = "baked-themes")]
 pub use baked::Baked;
+pub use builtin::{Builtin, FALLBACK_SLUG};
 pub use files::Files;
+pub use report::{Severity, Span, ThemeIssue, ThemeReport};
 
 const LIQUID_EXT: &str = "liquid";
 
@@ -22,7 +36,7 @@ pub trait Ingest {
     async fn ingest(data: Self::Data) -> Result<Theme>;
 }
 
-pub trait IngestImpl {
+pub trait IngestImpl: Sized {
     type Data;
 
     const KIND: &'static str;
@@ -30,7 +44,19 @@ pub trait IngestImpl {
     fn new(data: Self::Data) -> Self;
     fn slug(&self) -> Result<KString>;
     fn manifest(&self) -> impl Future<Output = Result<KString>>;
-    fn entries(self) -> impl Stream<Item = File>;
+    fn entries(&self) -> impl Stream<Item = File>;
+
+    /// Whether the theme has a file at `path`, relative to its dir.
+    fn has_file(&self, path: &str) -> bool;
+
+    /// The theme's own `assets` dir, if it has one.
+    fn assets(&self) -> Option<AssetDir>;
+
+    /// The theme with the given slug from the same source, used to resolve
+    /// a manifest's `parent`.
+    fn sibling(&self, slug: &str) -> Result<Self> {
+        bail!("{} themes can not have a parent, found {slug}", Self::KIND)
+    }
 }
 
 impl<I, D> Ingest for I
@@ -40,26 +66,109 @@ where
     type Data = D;
 
     async fn ingest(data: D) -> Result<Theme> {
-        async fn inner<I>(this: I, slug: KString) -> Result<Theme>
+        async fn inner<I>(mut this: I, slug: KString) -> Result<Theme>
         where
             I: IngestImpl,
         {
-            let manifest = this.manifest().await.context("manifest read error")?;
-            let manifest = toml::from_str(&manifest).context("manifest parse error")?;
+            let mut report = ThemeReport::new(slug.clone());
+            let mut layers = Vec::<Layer<I>>::new();
+            let mut lineage = vec![slug.clone()];
+
+            loop {
+                let layer_slug = lineage.last().expect("empty lineage").clone();
+                let source = this.manifest().await.context("manifest read error")?;
+                let manifest = match toml::from_str::<Table>(&source) {
+                    Ok(manifest) => manifest,
+                    Err(error) => {
+                        let file = format!("{layer_slug}/{}", STUFF.themes.manifest_path);
+                        let span = Span::locate_range(&source, error.span());
+                        report.error(file, span, error.message().to_string());
+                        return Err(report.into());
+                    }
+                };
 
-            let mut entries = pin!(this.entries());
-            let mut partials = EagerCompiler::<InMemorySource>::empty();
+                let entries = this.entries().collect::<Vec<_>>().await;
+                let layer = Layer {
+                    slug: layer_slug,
+                    source,
+                    manifest,
+                    entries,
+                    ingest: this,
+                };
 
-            while let Some(entry) = entries.next().await {
-                partials.add(entry.path, entry.text);
+                let next = match parent_slug(&layer.manifest) {
+                    Ok(None) => Ok(None),
+                    Ok(Some(parent)) if lineage.contains(&parent) => {
+                        lineage.push(parent);
+                        Err(format!("inheritance cycle {}", lineage.join(" -> ")))
+                    }
+                    Ok(Some(parent)) => {
+                        let sibling = layer.ingest.sibling(&parent);
+                        lineage.push(parent);
+                        sibling.map(Some).map_err(|error| format!("{error:#}"))
+                    }
+                    Err(error) => Err(error.to_string()),
+                }
+                .transpose();
+
+                let file = layer.manifest_file();
+                let span = layer.value_span(&["parent"]);
+                layers.push(layer);
+
+                match next {
+                    None => break,
+                    Some(Ok(next)) => this = next,
+                    Some(Err(message)) => {
+                        report.error(file, span, message);
+                        return Err(report.into());
+                    }
+                }
             }
 
-            let parser = Parser::new(partials)?;
+            let mut manifest = Table::new();
+            let mut partials = InMemorySource::new();
+
+            // Ancestors first, so that each theme overrides what it inherits.
+            for layer in layers.iter().rev() {
+                merge(&mut manifest, layer.manifest.clone());
+
+                for entry in &layer.entries {
+                    partials.add(&entry.path, desugar(&entry.text));
+                }
+            }
+
+            let language = Parser::language();
+            validate::validate(&layers, &manifest, &language, &mut report);
+
+            let manifest = match ThemeManifest::deserialize(manifest) {
+                Ok(manifest) => Some(manifest),
+                Err(error) => {
+                    report.error(layers[0].manifest_file(), None, error.message().to_string());
+                    None
+                }
+            };
+
+            let manifest = match manifest {
+                Some(manifest) if !report.has_errors() => manifest,
+                _ => return Err(report.into()),
+            };
+
+            report.log_warnings();
+
+            let parser = Parser::new(language, partials)?;
             let templates = Templates::new(&parser);
 
+            let assets: Box<[AssetDir]> = layers.iter().filter_map(|l| l.ingest.assets()).collect();
+            let asset_hashes = AssetHashes::new(&assets).await;
+
+            lineage.remove(0);
+
             let theme = Theme {
                 slug,
                 manifest,
+                lineage: lineage.into(),
+                assets,
+                asset_hashes,
                 templates,
             };
 
@@ -76,6 +185,27 @@ where
     }
 }
 
+fn parent_slug(manifest: &Table) -> Result<Option<KString>, &'static str> {
+    match manifest.get("parent") {
+        None => Ok(None),
+        Some(toml::Value::String(parent)) => Ok(Some(KString::from_ref(parent))),
+        Some(_) => Err("parent must be a string"),
+    }
+}
+
+/// Deep merges `over` into `base`, with tables merged key by key and any
+/// other value in `over` replacing the one in `base`.
+fn merge(base: &mut Table, over: Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(over)) => merge(base, over),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
 pub trait IngestMany {
     type Dataset;
 
@@ -116,7 +246,43 @@ where
     }
 }
 
-struct File {
+pub struct File {
     path: String,
     text: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_manifests() {
+        let mut base = toml::from_str::<Table>(
+            r#"
+            name = "Base"
+            layout = "_layouts/layout"
+            [tailwind]
+            input = "input.css"
+            config = "config.js"
+            "#,
+        )
+        .unwrap();
+        let over = toml::from_str::<Table>(
+            r#"
+            name = "Child"
+            parent = "base"
+            [tailwind]
+            input = "child.css"
+            "#,
+        )
+        .unwrap();
+
+        merge(&mut base, over);
+
+        assert_eq!(base["name"].as_str(), Some("Child"));
+        assert_eq!(base["parent"].as_str(), Some("base"));
+        assert_eq!(base["layout"].as_str(), Some("_layouts/layout"));
+        assert_eq!(base["tailwind"]["input"].as_str(), Some("child.css"));
+        assert_eq!(base["tailwind"]["config"].as_str(), Some("config.js"));
+    }
+}
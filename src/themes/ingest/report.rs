@@ -0,0 +1,124 @@
+use kstring::KString;
+use std::{error::Error, fmt, ops::Range};
+
+/// Problems found in a theme while ingesting it. A theme with any errors is
+/// not loaded, while warnings are only logged.
+#[derive(Debug, Clone)]
+pub struct ThemeReport {
+    slug: KString,
+    issues: Vec<ThemeIssue>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ThemeIssue {
+    pub severity: Severity,
+    /// The file the issue is in, relative to the themes dir.
+    pub file: String,
+    pub span: Option<Span>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A one-based line and column in a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl ThemeReport {
+    pub(super) fn new(slug: KString) -> Self {
+        Self {
+            slug,
+            issues: Vec::new(),
+        }
+    }
+
+    pub fn slug(&self) -> &KString {
+        &self.slug
+    }
+
+    pub fn issues(&self) -> &[ThemeIssue] {
+        &self.issues
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|i| i.severity == Severity::Error)
+    }
+
+    pub(super) fn error(&mut self, file: String, span: Option<Span>, message: String) {
+        self.push(Severity::Error, file, span, message);
+    }
+
+    pub(super) fn warning(&mut self, file: String, span: Option<Span>, message: String) {
+        self.push(Severity::Warning, file, span, message);
+    }
+
+    fn push(&mut self, severity: Severity, file: String, span: Option<Span>, message: String) {
+        self.issues.push(ThemeIssue {
+            severity,
+            file,
+            span,
+            message,
+        });
+    }
+
+    pub(super) fn log_warnings(&self) {
+        for issue in &self.issues {
+            if issue.severity == Severity::Warning {
+                tracing::warn!(target: "plethora::themes", theme = %self.slug, "{issue}");
+            }
+        }
+    }
+}
+
+impl fmt::Display for ThemeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "theme {} is invalid", self.slug)?;
+
+        for issue in &self.issues {
+            write!(f, "\n  {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ThemeReport {}
+
+impl fmt::Display for ThemeIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+
+        write!(f, "{severity}: {}", self.file)?;
+
+        if let Some(Span { line, column }) = self.span {
+            write!(f, ":{line}:{column}")?;
+        }
+
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl Span {
+    /// Locates a byte offset in `text`.
+    pub(super) fn locate(text: &str, offset: usize) -> Self {
+        let before = &text[..offset.min(text.len())];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+
+        Self { line, column }
+    }
+
+    pub(super) fn locate_range(text: &str, range: Option<Range<usize>>) -> Option<Self> {
+        range.map(|range| Self::locate(text, range.start))
+    }
+}
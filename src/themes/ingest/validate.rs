@@ -0,0 +1,340 @@
+use super::{
+    report::{Span, ThemeReport},
+    File, IngestImpl, LIQUID_EXT,
+};
+use crate::{
+    stuff::STUFF,
+    themes::{
+        assets::is_contained,
+        templates::{desugar, parse},
+    },
+};
+use camino::Utf8Path;
+use kstring::KString;
+use liquid_core::Language;
+use std::collections::HashSet;
+use toml::Table;
+use toml_edit::{ImDocument, TableLike};
+
+const MANIFEST_KEYS: &[&str] = &[
+    "name",
+    "parent",
+    "layout",
+    "error",
+    "not_found",
+    "tailwind",
+    "statuses",
+    "title",
+    "settings",
+];
+const TAILWIND_KEYS: &[&str] = &["input", "config"];
+const TITLE_KEYS: &[&str] = &["format", "separator"];
+const TEMPLATE_KEYS: &[&str] = &["layout", "error", "not_found"];
+const REFERENCE_TAGS: &[&str] = &["render", "include", "contain", "import"];
+
+/// One theme in a lineage, with the manifest and files it declares itself.
+pub(super) struct Layer<I> {
+    pub slug: KString,
+    pub source: KString,
+    pub manifest: Table,
+    pub entries: Vec<File>,
+    pub ingest: I,
+}
+
+impl<I> Layer<I> {
+    pub fn manifest_file(&self) -> String {
+        format!("{}/{}", self.slug, STUFF.themes.manifest_path)
+    }
+
+    fn document(&self) -> Option<ImDocument<&str>> {
+        ImDocument::parse(self.source.as_str()).ok()
+    }
+
+    /// The span of the value at `path` in this layer's manifest.
+    pub fn value_span(&self, path: &[&str]) -> Option<Span> {
+        let document = self.document()?;
+        let mut item = document.as_item();
+
+        for key in path {
+            item = item.get(key)?;
+        }
+
+        Span::locate_range(&self.source, item.span())
+    }
+}
+
+/// Checks a theme whose layers, nearest first, have been merged into
+/// `manifest`.
+pub(super) fn validate<I: IngestImpl>(
+    layers: &[Layer<I>],
+    manifest: &Table,
+    language: &Language,
+    report: &mut ThemeReport,
+) {
+    for layer in layers {
+        unknown_keys(layer, report);
+    }
+
+    let partials = effective_partials(layers);
+    let mut reachable = HashSet::new();
+
+    for key in TEMPLATE_KEYS {
+        let Some(name) = manifest.get(*key).and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let path = format!("{name}.{LIQUID_EXT}");
+
+        if !partials.iter().any(|(p, _, _)| **p == path) {
+            let (file, span) = defined_at(layers, &[key]);
+            report.error(file, span, format!("template {name} not found"));
+        }
+        reachable.insert(path);
+    }
+
+    if let Some(statuses) = manifest.get("statuses").and_then(|s| s.as_table()) {
+        for (code, name) in statuses {
+            let (file, span) = defined_at(layers, &["statuses", code]);
+            let is_error_status = code
+                .parse::<u16>()
+                .is_ok_and(|code| (400..600).contains(&code));
+
+            if !is_error_status {
+                let message = format!("status {code} is not an error status code");
+                report.error(file.clone(), span, message);
+            }
+
+            let Some(name) = name.as_str() else {
+                continue;
+            };
+            let path = format!("{name}.{LIQUID_EXT}");
+
+            if !partials.iter().any(|(p, _, _)| **p == path) {
+                report.error(file, span, format!("template {name} not found"));
+            }
+            reachable.insert(path);
+        }
+    }
+
+    let format = manifest
+        .get("title")
+        .and_then(|t| t.get("format"))
+        .and_then(|v| v.as_str());
+
+    if format.is_some_and(|format| !format.contains("{title}")) {
+        let (file, span) = defined_at(layers, &["title", "format"]);
+        report.error(file, span, "title format has no {title}".into());
+    }
+
+    for key in TAILWIND_KEYS {
+        let Some(path) = manifest
+            .get("tailwind")
+            .and_then(|t| t.get(*key))
+            .and_then(|v| v.as_str())
+        else {
+            continue;
+        };
+
+        let message = if !is_contained(Utf8Path::new(path)) {
+            format!("tailwind {key} {path} is outside the theme dir")
+        } else if !layers.iter().any(|layer| layer.ingest.has_file(path)) {
+            format!("tailwind {key} {path} not found")
+        } else {
+            continue;
+        };
+
+        let (file, span) = defined_at(layers, &["tailwind", key]);
+        report.error(file, span, message);
+    }
+
+    for (path, text, slug) in &partials {
+        if let Err(error) = parse(&desugar(text), language) {
+            let (span, message) = syntax_error(&error.to_string());
+            report.error(format!("{slug}/{path}"), span, message);
+        }
+
+        for (offset, name) in references(text) {
+            let reference = format!("{name}.{LIQUID_EXT}");
+
+            if !partials.iter().any(|(p, _, _)| **p == reference) {
+                let file = format!("{slug}/{path}");
+                let span = Some(Span::locate(text, offset));
+                report.error(file, span, format!("template {name} not found"));
+            }
+            reachable.insert(reference);
+        }
+    }
+
+    for (path, _, slug) in &partials {
+        if is_partial(path) && !reachable.contains(*path) {
+            let file = format!("{slug}/{path}");
+            report.warning(file, None, "partial is never rendered".into());
+        }
+    }
+}
+
+fn unknown_keys<I>(layer: &Layer<I>, report: &mut ThemeReport) {
+    let Some(document) = layer.document() else {
+        return;
+    };
+
+    let root = document.as_table();
+    check_keys(layer, root, MANIFEST_KEYS, "", report);
+
+    if let Some(tailwind) = root.get("tailwind").and_then(|t| t.as_table_like()) {
+        check_keys(layer, tailwind, TAILWIND_KEYS, "tailwind.", report);
+    }
+
+    if let Some(title) = root.get("title").and_then(|t| t.as_table_like()) {
+        check_keys(layer, title, TITLE_KEYS, "title.", report);
+    }
+}
+
+fn check_keys<I>(
+    layer: &Layer<I>,
+    table: &dyn TableLike,
+    known: &[&str],
+    prefix: &str,
+    report: &mut ThemeReport,
+) {
+    for (key, _) in table.iter() {
+        if known.contains(&key) {
+            continue;
+        }
+
+        let span = table
+            .key(key)
+            .and_then(|key| Span::locate_range(&layer.source, key.span()));
+        let message = format!("unknown manifest key {prefix}{key}");
+        report.warning(layer.manifest_file(), span, message);
+    }
+}
+
+/// The file and span of the nearest layer that sets the value at `path`.
+fn defined_at<I>(layers: &[Layer<I>], path: &[&str]) -> (String, Option<Span>) {
+    let layer = layers
+        .iter()
+        .find(|layer| {
+            let mut value = Some(&layer.manifest);
+            let (last, tables) = path.split_last().expect("empty path");
+
+            for key in tables {
+                value = value.and_then(|v| v.get(*key)).and_then(|v| v.as_table());
+            }
+            value.is_some_and(|v| v.contains_key(*last))
+        })
+        .unwrap_or(&layers[0]);
+
+    (layer.manifest_file(), layer.value_span(path))
+}
+
+/// Each template the theme ends up with, along with the slug of the layer
+/// it comes from.
+fn effective_partials<I>(layers: &[Layer<I>]) -> Vec<(&String, &str, &KString)> {
+    let mut seen = HashSet::new();
+    let mut partials = Vec::new();
+
+    for layer in layers {
+        for entry in &layer.entries {
+            if seen.insert(&entry.path) {
+                partials.push((&entry.path, entry.text.as_str(), &layer.slug));
+            }
+        }
+    }
+
+    partials
+}
+
+/// Finds the templates named by a string literal in a `render`, `include`,
+/// `contain` or `import` tag, along with the byte offset of each name.
+fn references(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.match_indices("{%").filter_map(|(start, _)| {
+        let rest = text[start + 2..].trim_start_matches('-').trim_start();
+        let tag = REFERENCE_TAGS.iter().find(|tag| {
+            rest.strip_prefix(**tag)
+                .is_some_and(|r| r.starts_with(char::is_whitespace))
+        })?;
+
+        let rest = rest[tag.len()..].trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let len = rest[1..].find(quote)?;
+        let offset = text.len() - rest.len() + 1;
+
+        Some((offset, &rest[1..1 + len]))
+    })
+}
+
+/// Splits a Liquid parse error into the position it points at and its
+/// message, dropping the excerpt of the source.
+fn syntax_error(error: &str) -> (Option<Span>, String) {
+    let span = error.split_once("--> ").and_then(|(_, rest)| {
+        let (line, column) = rest.lines().next()?.split_once(':')?;
+        Some(Span {
+            line: line.trim().parse().ok()?,
+            column: column.trim().parse().ok()?,
+        })
+    });
+
+    let message = error
+        .lines()
+        .find_map(|line| line.trim_start().strip_prefix("= "))
+        .or_else(|| error.trim_start_matches("liquid:").lines().next())
+        .unwrap_or(error)
+        .trim();
+
+    (span, message.to_string())
+}
+
+/// Whether a template is a partial, meant to be rendered by other templates
+/// rather than by the application, which is signified by an underscore at
+/// the start of any of its path segments.
+fn is_partial(path: &str) -> bool {
+    Utf8Path::new(path)
+        .components()
+        .any(|c| c.as_str().starts_with('_'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_references() {
+        let text = "{% render \"components/card\", title: x %}\n{%- include 'a' -%}\n{% render name %}{% contain \"b\" %}";
+        let found = references(text).collect::<Vec<_>>();
+
+        assert_eq!(found, vec![(11, "components/card"), (54, "a"), (90, "b")]);
+        assert_eq!(
+            Span::locate(text, 54),
+            Span {
+                line: 2,
+                column: 14
+            }
+        );
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let error = "liquid:  --> 2:4\n  |\n2 | {% nope %}\n  |    ^--^\n  |\n  = Unknown tag.\n  with:\n    requested=nope\n";
+        assert_eq!(
+            syntax_error(error),
+            (
+                Some(Span { line: 2, column: 4 }),
+                "Unknown tag.".to_string()
+            )
+        );
+
+        let error = "liquid: Unexpected end\n";
+        assert_eq!(syntax_error(error), (None, "Unexpected end".to_string()));
+    }
+
+    #[test]
+    fn partials_and_paths() {
+        assert!(is_partial("_layouts/layout.liquid"));
+        assert!(is_partial("components/_card.liquid"));
+        assert!(!is_partial("posts/index.liquid"));
+
+        assert!(is_contained(Utf8Path::new("_tailwind/input.css")));
+        assert!(!is_contained(Utf8Path::new("../other/input.css")));
+        assert!(!is_contained(Utf8Path::new("/etc/passwd")));
+    }
+}
@@ -1,27 +1,50 @@
 use self::ingest::Ingest;
 use crate::{reload::Reload, stuff::STUFF, styles::Styles};
-use anyhow::Result;
-use camino::{Utf8Path, Utf8PathBuf};
+use anyhow::{bail, Result};
+use arc_swap::ArcSwap;
+use camino::Utf8PathBuf;
 use dashmap::DashMap;
 use ingest::IngestMany;
 use kstring::KString;
+use serde::Serialize;
 use std::{fmt, future::Future, ops::Deref, sync::Arc};
 
+mod assets;
 mod builder;
+mod cache;
 mod ingest;
+mod page;
+mod settings;
 mod templates;
 mod theme;
+mod title;
 
+pub use assets::{AssetHashes, ThemeAsset, ASSETS_DIR};
 pub use builder::ThemesBuilder;
+pub(crate) use cache::{CachedFragment, FragmentCache, FragmentKey};
+pub use ingest::{Severity, Span, ThemeIssue, ThemeReport};
 pub use liquid::object as props;
+pub use page::Page;
 pub use theme::{Theme, ThemeManifest, ThemeManifestTailwind};
+pub use title::{TitleFormat, TitleParts};
 
 #[derive(Debug, Clone)]
 pub struct Themes {
     map: Arc<DashMap<KString, Theme>>,
+    listing: Arc<ArcSwap<Vec<ThemeListing>>>,
+    fallback: Arc<Theme>,
+    overrides: settings::Overrides,
+    fragments: FragmentCache,
     styles: Styles,
 }
 
+/// A summary of an available theme, as used to build a theme picker.
+#[derive(Debug, Clone, Serialize)]
+pub struct ThemeListing {
+    pub slug: KString,
+    pub name: KString,
+}
+
 impl Themes {
     pub async fn new(styles: Styles) -> Result<Self> {
         Self::builder(styles).build().await
@@ -31,14 +54,51 @@ impl Themes {
         ThemesBuilder::new(styles)
     }
 
-    pub fn get(&self, slug: &str) -> Option<ThemeGuard> {
-        self.map.get(slug).map(ThemeGuard)
+    pub fn get(&self, slug: &str) -> Option<ThemeGuard<'_>> {
+        match self.map.get(slug) {
+            Some(theme) => Some(ThemeGuard(Guard::Map(theme))),
+            None => (slug == ingest::FALLBACK_SLUG).then(|| self.fallback()),
+        }
+    }
+
+    /// The minimal theme built into the crate, which is always available
+    /// even when no themes could be loaded.
+    pub fn fallback(&self) -> ThemeGuard<'_> {
+        ThemeGuard(Guard::Fallback(&self.fallback))
+    }
+
+    /// Fails unless a theme with the given slug is loaded, listing the
+    /// themes that are.
+    pub fn ensure(&self, slug: &str) -> Result<()> {
+        if self.map.contains_key(slug) {
+            return Ok(());
+        }
+
+        let listing = self.listing();
+        let available = listing
+            .iter()
+            .map(|theme| theme.slug.as_str())
+            .collect::<Vec<_>>();
+
+        if available.is_empty() {
+            bail!("theme {slug} not found, no themes are loaded");
+        }
+        bail!(
+            "theme {slug} not found, available themes are {}",
+            available.join(", ")
+        );
     }
 
     pub fn iter(&self) -> ThemeIter<'_> {
         ThemeIter(self.map.iter())
     }
 
+    /// The available themes, sorted by slug. Unlike [`iter`](Self::iter),
+    /// this is safe to call while holding a [`ThemeGuard`].
+    pub fn listing(&self) -> Arc<Vec<ThemeListing>> {
+        self.listing.load_full()
+    }
+
     async fn ingest<I: Ingest>(&self, data: I::Data) -> Result<()> {
         let theme = I::ingest(data).await?;
         self.insert(theme).await
@@ -50,15 +110,41 @@ impl Themes {
 
     async fn insert(&self, theme: Theme) -> Result<()> {
         self.styles.compile(&theme).await?;
+        settings::check_configured(&theme);
+        self.fragments.flush(&theme.slug);
         self.map.insert(theme.slug.clone(), theme);
+        self.relist();
         Ok(())
     }
+
+    /// The slugs of the themes that inherit from `slug`, directly or not.
+    fn descendants(&self, slug: &str) -> Vec<KString> {
+        self.map
+            .iter()
+            .filter(|theme| theme.lineage.iter().any(|s| s == slug))
+            .map(|theme| theme.slug.clone())
+            .collect()
+    }
+
+    fn relist(&self) {
+        let mut listing = self
+            .map
+            .iter()
+            .map(|theme| ThemeListing {
+                slug: theme.slug.clone(),
+                name: theme.manifest.name.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        listing.sort_by(|a, b| a.slug.cmp(&b.slug));
+        self.listing.store(Arc::new(listing));
+    }
 }
 
 #[allow(clippy::manual_async_fn)]
 impl Reload for Themes {
-    fn dir(&self) -> Option<&'static Utf8Path> {
-        Some(&STUFF.themes.dir)
+    fn dirs(&self) -> Vec<Utf8PathBuf> {
+        vec![STUFF.themes.dir.to_path_buf()]
     }
 
     fn reload(&self, mut path: Utf8PathBuf) -> impl Future<Output = Result<()>> + Send + 'static {
@@ -68,12 +154,40 @@ impl Reload for Themes {
             while path.parent() != Some(&STUFF.themes.dir) {
                 path.pop();
             }
-            this.ingest::<ingest::Files>(path).await
+
+            let slug = path.file_name().map(KString::from_ref);
+            this.ingest::<ingest::Files>(path).await?;
+
+            let Some(slug) = slug else {
+                return Ok(());
+            };
+
+            // One broken child shouldn't keep the others on the old parent.
+            let mut errors = Vec::new();
+            for child in this.descendants(&slug) {
+                let path = STUFF.themes.dir.join(child.as_str());
+                if let Err(error) = this.ingest::<ingest::Files>(path).await {
+                    errors.push(format!("{child}: {error:#}"));
+                }
+            }
+
+            if !errors.is_empty() {
+                bail!(
+                    "failed to reload themes based on {slug}\n{}",
+                    errors.join("\n")
+                );
+            }
+            Ok(())
         }
     }
 }
 
-pub struct ThemeGuard<'a>(dashmap::mapref::one::Ref<'a, KString, Theme>);
+pub struct ThemeGuard<'a>(Guard<'a>);
+
+enum Guard<'a> {
+    Map(dashmap::mapref::one::Ref<'a, KString, Theme>),
+    Fallback(&'a Theme),
+}
 
 impl fmt::Debug for ThemeGuard<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -85,7 +199,10 @@ impl Deref for ThemeGuard<'_> {
     type Target = Theme;
 
     fn deref(&self) -> &Self::Target {
-        self.0.deref()
+        match &self.0 {
+            Guard::Map(theme) => theme.deref(),
+            Guard::Fallback(theme) => theme,
+        }
     }
 }
 
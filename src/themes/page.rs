@@ -0,0 +1,268 @@
+use axum::{
+    body::Body,
+    http::{HeaderName, HeaderValue},
+    response::{Html, IntoResponse, Response},
+};
+use bytes::Bytes;
+use kstring::KString;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use std::{convert::Infallible, fmt, io, mem};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// How many chunks a streamed page can render ahead of the client reading
+/// them.
+const STREAM_CHUNKS: usize = 4;
+
+/// The page title of a fragment, percent-encoded since titles needn't be
+/// ASCII.
+const TITLE: HeaderName = HeaderName::from_static("plethora-title");
+
+/// A rendered page, which the response body is sent from, either in chunks
+/// of about [`CHUNK_SIZE`] rather than one contiguous string, or as those
+/// chunks are rendered.
+pub struct Page {
+    body: PageBody,
+    title: Option<KString>,
+}
+
+enum PageBody {
+    Chunks(Vec<Bytes>),
+    Stream(mpsc::Receiver<io::Result<Bytes>>),
+}
+
+impl Page {
+    pub(super) fn new(chunks: Chunks) -> Self {
+        Self {
+            body: PageBody::Chunks(chunks.finish()),
+            title: None,
+        }
+    }
+
+    /// A page rendered as a string, such as a fragment.
+    pub(super) fn whole(html: String) -> Self {
+        Self {
+            body: PageBody::Chunks(vec![Bytes::from(html)]),
+            title: None,
+        }
+    }
+
+    /// A page that `render` writes in the background, each chunk of which is
+    /// sent as soon as it's written. Since the response has started by then,
+    /// an error can only cut it short.
+    pub(super) fn stream<F>(render: F) -> Self
+    where
+        F: FnOnce(&mut dyn io::Write) -> anyhow::Result<()> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(STREAM_CHUNKS);
+
+        tokio::task::spawn_blocking(move || {
+            let mut sender = Sender {
+                tx,
+                buf: Vec::with_capacity(CHUNK_SIZE),
+            };
+
+            let result = render(&mut sender).and_then(|()| Ok(io::Write::flush(&mut sender)?));
+            if let Err(error) = result {
+                // The client is gone, so there's no one to tell.
+                if sender.tx.is_closed() {
+                    return;
+                }
+
+                tracing::error!("failed to render page: {error:?}");
+                let error = io::Error::other(format!("{error:#}"));
+                sender.tx.blocking_send(Err(error)).ok();
+            }
+        });
+
+        Self {
+            body: PageBody::Stream(rx),
+            title: None,
+        }
+    }
+
+    /// Sends the title in a response header, for fragments rendered without
+    /// the layout that would otherwise show it.
+    pub(super) fn with_title(self, title: Option<KString>) -> Self {
+        Self { title, ..self }
+    }
+
+    pub fn into_body(self) -> Body {
+        match self.body {
+            PageBody::Chunks(chunks) => {
+                let chunks = chunks
+                    .into_iter()
+                    .filter(|chunk| !chunk.is_empty())
+                    .map(Ok::<_, Infallible>);
+
+                Body::from_stream(futures::stream::iter(chunks))
+            }
+            PageBody::Stream(rx) => Body::from_stream(ReceiverStream::new(rx)),
+        }
+    }
+}
+
+impl IntoResponse for Page {
+    fn into_response(self) -> Response {
+        let title = self.title.as_ref().and_then(|title| {
+            let encoded = utf8_percent_encode(title, NON_ALPHANUMERIC).to_string();
+            HeaderValue::try_from(encoded).ok()
+        });
+        let mut response = Html(self.into_body()).into_response();
+
+        if let Some(title) = title {
+            response.headers_mut().insert(TITLE, title);
+        }
+        response
+    }
+}
+
+impl fmt::Debug for Page {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Page");
+
+        match &self.body {
+            PageBody::Chunks(chunks) => {
+                debug.field("len", &chunks.iter().map(Bytes::len).sum::<usize>())
+            }
+            PageBody::Stream(_) => debug.field("len", &"streamed"),
+        };
+        debug.field("title", &self.title).finish()
+    }
+}
+
+/// A writer that collects its output in chunks of about [`CHUNK_SIZE`], so a
+/// large page is never held in one contiguous buffer.
+#[derive(Debug, Default)]
+pub(super) struct Chunks {
+    chunks: Vec<Bytes>,
+    buf: Vec<u8>,
+}
+
+impl Chunks {
+    fn finish(mut self) -> Vec<Bytes> {
+        if !self.buf.is_empty() {
+            self.chunks.push(Bytes::from(self.buf));
+        }
+        self.chunks
+    }
+}
+
+impl io::Write for Chunks {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(bytes);
+
+        if self.buf.len() >= CHUNK_SIZE {
+            let buf = mem::replace(&mut self.buf, Vec::with_capacity(CHUNK_SIZE));
+            self.chunks.push(Bytes::from(buf));
+        }
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A writer that sends its output down a [`Page::stream`] in chunks of
+/// [`CHUNK_SIZE`], waiting while the client is behind.
+struct Sender {
+    tx: mpsc::Sender<io::Result<Bytes>>,
+    buf: Vec<u8>,
+}
+
+impl Sender {
+    fn send(&mut self) -> io::Result<()> {
+        let buf = mem::replace(&mut self.buf, Vec::with_capacity(CHUNK_SIZE));
+        self.tx
+            .blocking_send(Ok(Bytes::from(buf)))
+            .map_err(|_| io::ErrorKind::BrokenPipe.into())
+    }
+}
+
+impl io::Write for Sender {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        let len = bytes.len().min(CHUNK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&bytes[..len]);
+
+        if self.buf.len() == CHUNK_SIZE {
+            self.send()?;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        self.send()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use std::{io::Write, sync::mpsc as std_mpsc, time::Duration};
+
+    #[test]
+    fn chunks() {
+        let mut chunks = Chunks::default();
+        let line = "é".repeat(CHUNK_SIZE / 3);
+
+        for _ in 0..5 {
+            chunks.write_all(line.as_bytes()).unwrap();
+        }
+
+        let parts = chunks.finish();
+        assert_eq!(parts.len(), 3);
+        assert!(parts
+            .iter()
+            .all(|part| part.len() <= CHUNK_SIZE + line.len()));
+        assert_eq!(parts.concat(), line.repeat(5).as_bytes());
+    }
+
+    #[tokio::test]
+    async fn streams_before_rendering_is_done() {
+        let (done_tx, done_rx) = std_mpsc::channel();
+        let page = Page::stream(move |writer| {
+            writer.write_all(&[b'a'; CHUNK_SIZE + 1])?;
+            done_rx.recv()?;
+            writer.write_all(b"tail")?;
+            Ok(())
+        });
+
+        let mut body = page.into_body().into_data_stream();
+        let next = tokio::time::timeout(Duration::from_secs(5), body.next());
+        let first = next
+            .await
+            .expect("first chunk sent early")
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.len(), CHUNK_SIZE);
+
+        done_tx.send(()).unwrap();
+        let rest = body.map(|chunk| chunk.unwrap()).collect::<Vec<_>>().await;
+        assert_eq!(rest.concat(), b"atail");
+    }
+
+    #[tokio::test]
+    async fn cuts_the_stream_short_on_error() {
+        let page = Page::stream(|writer| {
+            writer.write_all(b"head")?;
+            writer.flush()?;
+            anyhow::bail!("broken layout")
+        });
+
+        let chunks = page
+            .into_body()
+            .into_data_stream()
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].as_ref().unwrap().as_ref(), b"head");
+        assert!(chunks[1].is_err());
+    }
+}
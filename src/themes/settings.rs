@@ -0,0 +1,133 @@
+//! Theme settings, declared with their defaults in the `[settings]` table of
+//! a theme's manifest. The application can override them per theme, either
+//! in `themes.settings.<slug>` or at runtime, as long as each override has
+//! the same type as the default it replaces. Keys in `Stuff` are lowercased,
+//! so settings are best named in snake_case.
+
+use super::{Theme, Themes};
+use crate::stuff::STUFF;
+use anyhow::{bail, Context, Result};
+use dashmap::DashMap;
+use kstring::KString;
+use liquid::Object;
+use serde::Serialize;
+use std::sync::Arc;
+use toml::{Table, Value};
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct Overrides(Arc<DashMap<KString, Table>>);
+
+impl Themes {
+    /// The settings of a theme, with any overrides applied over the defaults
+    /// from its manifest.
+    pub fn settings(&self, theme: &Theme) -> Object {
+        let defaults = &theme.manifest.settings;
+        let mut settings = defaults.clone();
+
+        if let Some(overrides) = STUFF.themes.settings.get(theme.slug.as_str()) {
+            apply(defaults, &mut settings, overrides);
+        }
+        if let Some(overrides) = self.overrides.0.get(theme.slug.as_str()) {
+            apply(defaults, &mut settings, &overrides);
+        }
+
+        liquid::model::to_object(&settings).expect("invalid theme settings")
+    }
+
+    /// Overrides a setting of a loaded theme until the process exits.
+    pub fn set_setting(&self, slug: &str, key: &str, value: impl Serialize) -> Result<()> {
+        let value = Value::try_from(value).context("invalid setting value")?;
+        let value = {
+            let theme = self
+                .get(slug)
+                .with_context(|| format!("unknown theme {slug}"))?;
+            coerce(&theme.manifest.settings, key, value)?
+        };
+
+        self.overrides
+            .0
+            .entry(KString::from_ref(slug))
+            .or_default()
+            .insert(key.to_string(), value);
+        self.fragments.flush(slug);
+
+        Ok(())
+    }
+
+    /// Removes a runtime override, restoring the setting to its configured
+    /// or default value.
+    pub fn reset_setting(&self, slug: &str, key: &str) {
+        if let Some(mut overrides) = self.overrides.0.get_mut(slug) {
+            overrides.remove(key);
+        }
+        self.fragments.flush(slug);
+    }
+}
+
+/// Warns about overrides in `Stuff` that don't match the theme's settings,
+/// which are otherwise ignored.
+pub(super) fn check_configured(theme: &Theme) {
+    let Some(overrides) = STUFF.themes.settings.get(theme.slug.as_str()) else {
+        return;
+    };
+
+    for (key, value) in overrides {
+        if let Err(error) = coerce(&theme.manifest.settings, key, value.clone()) {
+            tracing::warn!(target: "plethora::themes", theme = %theme.slug, "{error}");
+        }
+    }
+}
+
+fn apply(defaults: &Table, settings: &mut Table, overrides: &Table) {
+    for (key, value) in overrides {
+        if let Ok(value) = coerce(defaults, key, value.clone()) {
+            settings.insert(key.clone(), value);
+        }
+    }
+}
+
+/// Checks that `value` can replace the default for `key`, widening integers
+/// where the default is a float.
+fn coerce(defaults: &Table, key: &str, value: Value) -> Result<Value> {
+    let Some(default) = defaults.get(key) else {
+        bail!("unknown setting {key}");
+    };
+
+    match (default, value) {
+        (Value::Float(_), Value::Integer(i)) => Ok(Value::Float(i as f64)),
+        (default, value) if default.same_type(&value) => Ok(value),
+        (default, value) => bail!(
+            "setting {key} must be a {}, not a {}",
+            default.type_str(),
+            value.type_str()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coerce_types() {
+        let defaults = toml::from_str::<Table>(
+            r#"
+            accent = "indigo"
+            sidebar = true
+            width = 1.5
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            coerce(&defaults, "accent", Value::from("red")).unwrap(),
+            Value::from("red")
+        );
+        assert_eq!(
+            coerce(&defaults, "width", Value::from(2)).unwrap(),
+            Value::from(2.0)
+        );
+        assert!(coerce(&defaults, "sidebar", Value::from("yes")).is_err());
+        assert!(coerce(&defaults, "logo", Value::from("x.png")).is_err());
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{
+    languages::Languages,
+    themes::{AssetHashes, FragmentCache},
+};
+use kstring::KString;
+use liquid_core::{Error, Result, Runtime};
+
+/// State that tags and filters need while rendering, but which is not
+/// exposed to templates as a global.
+#[derive(Debug, Clone)]
+pub struct Context {
+    pub languages: Languages,
+    pub language: KString,
+    pub theme: KString,
+    pub theme_assets: AssetHashes,
+    pub template: KString,
+    pub cache: FragmentCache,
+    /// The `{% fragment %}` the template is being rendered for.
+    pub fragment: Option<KString>,
+}
+
+impl Context {
+    pub fn install(self, runtime: &dyn Runtime) {
+        runtime.registers().get_mut::<Register>().0 = Some(self);
+    }
+
+    pub fn get(runtime: &dyn Runtime) -> Result<Self> {
+        runtime
+            .registers()
+            .get_mut::<Register>()
+            .0
+            .clone()
+            .ok_or_else(|| Error::with_msg("Render context missing"))
+    }
+}
+
+#[derive(Default)]
+struct Register(Option<Context>);
@@ -0,0 +1,38 @@
+use super::{l, Ex};
+use std::fmt;
+
+pub trait Apply: Send + Sync + 'static {
+    fn apply(&self, input: &dyn l::ValueView, runtime: &dyn l::Runtime) -> l::Result<l::Value>;
+}
+
+pub struct ApplyFn<C>(
+    pub C,
+    pub fn(&C, &dyn l::ValueView, &dyn l::Runtime) -> l::Result<l::Value>,
+);
+
+impl<C> Apply for ApplyFn<C>
+where
+    C: Send + Sync + 'static,
+{
+    fn apply(&self, input: &dyn l::ValueView, runtime: &dyn l::Runtime) -> l::Result<l::Value> {
+        self.1(&self.0, input, runtime)
+    }
+}
+
+/// A parsed filter, named so that it can be displayed in error traces.
+pub struct Applied<A> {
+    pub name: &'static str,
+    pub apply: A,
+}
+
+impl<A: Apply> l::Filter for Ex<Applied<A>> {
+    fn evaluate(&self, input: &dyn l::ValueView, runtime: &dyn l::Runtime) -> l::Result<l::Value> {
+        self.0.apply.apply(input, runtime)
+    }
+}
+
+impl<A> fmt::Display for Ex<Applied<A>> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.name)
+    }
+}
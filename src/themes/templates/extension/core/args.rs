@@ -29,6 +29,22 @@ impl<'a> Args<'a> {
             .into_result()
     }
 
+    /// An expression, along with its source when it's a variable path such
+    /// as `ui.card`, which names what it refers to rather than its value.
+    pub fn expression_with_path(&mut self) -> l::Result<(l::Expression, Option<KString>)> {
+        let token = self.iter.expect_next("Expression expected.")?;
+        let source = token.as_str();
+        let is_path = source.split('.').all(|part| {
+            part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        });
+        let path = is_path.then(|| KString::from_ref(source));
+
+        Ok((token.expect_value().into_result()?, path))
+    }
+
     pub fn literal(&mut self) -> l::Result<l::Value> {
         self.iter
             .expect_next("Literal expected.")?
@@ -69,6 +85,39 @@ impl<'a> Args<'a> {
         Ok(Kwargs { map })
     }
 
+    /// Parameters up to the end of the tag, as `name` or `name: literal`
+    /// separated by commas.
+    pub fn params(mut self) -> l::Result<Vec<(KString, Option<l::Value>)>> {
+        let mut params = Vec::new();
+
+        while let Some(token) = self.iter.next() {
+            let name = token.expect_identifier().into_result()?;
+            let mut next = self.iter.next();
+
+            let default = match next.take().map(|t| t.expect_str(":")) {
+                Some(l::parser::TryMatchToken::Matches(())) => {
+                    let default = self.literal()?;
+                    next = self.iter.next();
+                    Some(default)
+                }
+                Some(l::parser::TryMatchToken::Fails(token)) => {
+                    next = Some(token);
+                    None
+                }
+                None => None,
+            };
+
+            params.push((KString::from_ref(name), default));
+
+            match next {
+                Some(token) => token.expect_str(",").into_result()?,
+                None => break,
+            }
+        }
+
+        Ok(params)
+    }
+
     pub fn comma(&mut self) -> l::Result<()> {
         self.exact("Comma expected.", ",")
     }
@@ -100,6 +149,34 @@ impl<'a: 'b, 'b> Body<'a, 'b> {
     }
 }
 
+pub struct FilterArgs<'a> {
+    args: l::parser::FilterArguments<'a>,
+}
+
+impl<'a> FilterArgs<'a> {
+    pub(super) fn new(args: l::parser::FilterArguments<'a>) -> Self {
+        Self { args }
+    }
+
+    pub fn kwargs(&mut self) -> Kwargs {
+        let map = self
+            .args
+            .keyword
+            .by_ref()
+            .map(|(k, v)| (KString::from_ref(k), v))
+            .collect();
+
+        Kwargs { map }
+    }
+
+    pub fn empty(mut self) -> l::Result<()> {
+        if self.args.positional.next().is_some() || self.args.keyword.next().is_some() {
+            return Err(l::Error::with_msg("Unexpected filter argument."));
+        }
+        Ok(())
+    }
+}
+
 pub type EvaluatedKwargs<'a> = ahash::HashMap<KStringCow<'a>, l::ValueCow<'a>>;
 
 #[derive(Default)]
@@ -1,12 +1,14 @@
 use liquid_core as l;
 
+mod apply;
 mod args;
 mod render;
 mod traits;
 
-pub use args::{Args, Body, EvaluatedKwargs, Kwargs};
+pub use apply::{Applied, Apply, ApplyFn};
+pub use args::{Args, Body, EvaluatedKwargs, FilterArgs, Kwargs};
 pub use render::{Render, RenderFn};
-pub use traits::{Block, Tag};
+pub use traits::{Block, Filter, Tag};
 
 #[derive(Clone)]
 pub struct Ex<T>(pub T);
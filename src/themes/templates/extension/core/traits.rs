@@ -1,4 +1,4 @@
-use super::{l, Args, Body, Ex, Render};
+use super::{l, Applied, Apply, Args, Body, Ex, FilterArgs, Render};
 
 pub trait Tag: Clone + Send + Sync + 'static {
     const NAME: &'static str;
@@ -68,3 +68,41 @@ impl<B: Block> l::BlockReflection for Ex<B> {
         ""
     }
 }
+
+pub trait Filter: Clone + Send + Sync + 'static {
+    const NAME: &'static str;
+    fn filter(&self, args: FilterArgs) -> l::Result<impl Apply>;
+}
+
+impl<F: Filter> l::ParseFilter for Ex<F> {
+    fn parse(&self, arguments: l::parser::FilterArguments) -> l::Result<Box<dyn l::Filter>> {
+        let args = FilterArgs::new(arguments);
+        let apply = self.0.filter(args)?;
+        Ok(Box::new(Ex(Applied {
+            name: F::NAME,
+            apply,
+        })))
+    }
+
+    fn reflection(&self) -> &dyn l::FilterReflection {
+        self
+    }
+}
+
+impl<F: Filter> l::FilterReflection for Ex<F> {
+    fn name(&self) -> &str {
+        F::NAME
+    }
+
+    fn description(&self) -> &str {
+        ""
+    }
+
+    fn positional_parameters(&self) -> &'static [l::parser::ParameterReflection] {
+        &[]
+    }
+
+    fn keyword_parameters(&self) -> &'static [l::parser::ParameterReflection] {
+        &[]
+    }
+}
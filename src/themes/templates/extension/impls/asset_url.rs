@@ -0,0 +1,24 @@
+use super::prelude::*;
+use crate::themes::assets;
+
+#[derive(Clone)]
+pub struct AssetUrl;
+
+impl Filter for AssetUrl {
+    const NAME: &'static str = "asset_url";
+
+    fn filter(&self, args: FilterArgs) -> Result<impl Apply> {
+        args.empty()?;
+
+        Ok(ApplyFn((), |_, input, runtime| {
+            let path = input.to_kstr();
+            let Context {
+                theme,
+                theme_assets,
+                ..
+            } = Context::get(runtime)?;
+
+            Ok(Value::scalar(assets::url(&theme, &theme_assets, &path)))
+        }))
+    }
+}
@@ -0,0 +1,161 @@
+use super::{
+    content_for, fragment, head, head::HeadTag, js, prelude::*, render::CacheFrame, title,
+};
+use crate::themes::{CachedFragment, FragmentKey};
+use liquid_core::{error::ResultLiquidReplaceExt, Renderable as _};
+use std::time::Duration;
+
+/// Caches the output of its body by theme, template, language and key, for
+/// `ttl` seconds if given. What tags such as `{% title %}` and `{% js %}` in
+/// the body leave for the layout is cached with it and done again on every
+/// hit, as is a `{% fragment %}` in it. Output that differs by user must have
+/// the user in the key, and neither `{% csrf %}` nor `csrf_token` can be
+/// cached at all.
+#[derive(Clone)]
+pub struct Cache;
+
+impl Block for Cache {
+    const START: &'static str = "cache";
+    const END: &'static str = "endcache";
+
+    fn block(&self, mut args: Args, body: Body, language: &Language) -> Result<impl Render> {
+        let key = args.expression()?;
+        let ttl = match args.comma() {
+            Ok(()) => {
+                let option = args.identifier()?;
+                if option != "ttl" {
+                    return Error::with_msg(format!("Unknown cache option {option}.")).into_err();
+                }
+                args.exact("Colon expected.", ":")?;
+                Some(args.expression()?)
+            }
+            Err(_) => None,
+        };
+        let template = body.template(language)?;
+
+        Ok(RenderFn(
+            (key, ttl, template),
+            |(key, ttl, template), writer, runtime| {
+                let Context {
+                    theme,
+                    template: page,
+                    language,
+                    cache,
+                    ..
+                } = Context::get(runtime)?;
+
+                let key = FragmentKey {
+                    theme,
+                    template: page,
+                    language,
+                    key: key.evaluate(runtime)?.to_kstr().into_owned(),
+                };
+
+                if let Some(cached) = cache.get(&key) {
+                    writer
+                        .write_all(cached.html.as_bytes())
+                        .replace("Failed to render")?;
+
+                    for effect in cached.effects.iter().cloned() {
+                        effect.apply(runtime);
+                    }
+                    return Ok(());
+                }
+
+                let ttl = ttl
+                    .as_ref()
+                    .map(|ttl| seconds(ttl.evaluate(runtime)?))
+                    .transpose()?;
+
+                let mut html = Vec::new();
+                runtime
+                    .registers()
+                    .get_mut::<Recording>()
+                    .0
+                    .push(Vec::new());
+                let frame = CacheFrame::new(runtime);
+                let rendered = template.render_to(&mut html, &frame);
+                let effects = runtime.registers().get_mut::<Recording>().0.pop();
+                rendered?;
+
+                if frame.read_csrf_token() {
+                    return Error::with_msg("`csrf_token` can't be cached, it's per user")
+                        .into_err();
+                }
+
+                writer.write_all(&html).replace("Failed to render")?;
+
+                let html = String::from_utf8(html).replace("Invalid cached output")?;
+                let effects = effects.unwrap_or_default();
+                cache.insert(key, CachedFragment { html, effects }, ttl);
+                Ok(())
+            },
+        ))
+    }
+}
+
+/// What a tag in a `{% cache %}` block leaves for the layout besides its
+/// output.
+#[derive(Debug, Clone)]
+pub enum Effect {
+    Title(KString),
+    TitleSegment(KString),
+    Js(KString),
+    Section(KString, String),
+    Head(HeadTag, KString),
+    Fragment(KString, String),
+}
+
+impl Effect {
+    fn apply(self, runtime: &dyn Runtime) {
+        match self {
+            Self::Title(value) => title::set_title(runtime, value),
+            Self::TitleSegment(segment) => title::push_segment(runtime, segment),
+            Self::Js(js) => js::include(runtime, js),
+            Self::Section(name, html) => content_for::append(runtime, name, &html),
+            Self::Head(tag, value) => head::push(runtime, tag, value),
+            Self::Fragment(name, html) => fragment::keep(runtime, &name, html),
+        }
+    }
+
+    /// Roughly how much memory the effect takes in the cache.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Title(value) | Self::TitleSegment(value) | Self::Js(value) => value.len(),
+            Self::Section(name, html) | Self::Fragment(name, html) => name.len() + html.len(),
+            Self::Head(_, value) => value.len(),
+        }
+    }
+}
+
+/// Records `effect` for each `{% cache %}` block being rendered, including
+/// those the current one is nested in.
+pub(super) fn record(runtime: &dyn Runtime, effect: impl FnOnce() -> Effect) {
+    let mut recording = runtime.registers().get_mut::<Recording>();
+
+    if let Some((last, outer)) = recording.0.split_last_mut() {
+        let effect = effect();
+        for effects in outer {
+            effects.push(effect.clone());
+        }
+        last.push(effect);
+    }
+}
+
+/// Whether a `{% cache %}` block is being rendered.
+pub(super) fn is_recording(runtime: &dyn Runtime) -> bool {
+    !runtime.registers().get_mut::<Recording>().0.is_empty()
+}
+
+/// The effects recorded so far for each `{% cache %}` block being rendered,
+/// innermost last.
+#[derive(Default)]
+struct Recording(Vec<Vec<Effect>>);
+
+fn seconds(ttl: ValueCow) -> Result<Duration> {
+    ttl.as_scalar()
+        .and_then(|s| s.to_integer())
+        .and_then(|s| u64::try_from(s).ok())
+        .map(Duration::from_secs)
+        .ok_or_else(|| Error::with_msg("Cache ttl must be a number of seconds."))
+}
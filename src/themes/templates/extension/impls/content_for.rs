@@ -0,0 +1,76 @@
+use super::{
+    cache::{self, Effect},
+    prelude::*,
+};
+use ahash::AHashMap;
+use liquid_core::{error::ResultLiquidReplaceExt, Renderable as _};
+use std::mem;
+
+/// Renders its body into a named section for the layout instead of in
+/// place. Sections with the same name are appended in order.
+#[derive(Clone)]
+pub struct ContentFor;
+
+impl Block for ContentFor {
+    const START: &'static str = "content_for";
+    const END: &'static str = "endcontent_for";
+
+    fn block(&self, mut args: Args, body: Body, language: &Language) -> Result<impl Render> {
+        let name = args.string_literal()?;
+        let template = body.template(language)?;
+        args.empty()?;
+
+        Ok(RenderFn(
+            (name, template),
+            |(name, template), _, runtime| {
+                let mut html = Vec::new();
+                template.render_to(&mut html, runtime)?;
+
+                let html = String::from_utf8(html).replace("Invalid section output")?;
+                append(runtime, name.clone(), &html);
+                Ok(())
+            },
+        ))
+    }
+}
+
+pub(super) fn append(runtime: &dyn Runtime, name: KString, html: &str) {
+    cache::record(runtime, || Effect::Section(name.clone(), html.to_string()));
+
+    let mut register = runtime.registers().get_mut::<Register>();
+    register.0.entry(name).or_default().push_str(html);
+}
+
+/// Outputs a section from `{% content_for %}` in the layout, or nothing if
+/// the page didn't render one. The sections are also in the `content_for`
+/// global, for layouts that only show a section's wrapper when it's there.
+#[derive(Clone)]
+pub struct Yield;
+
+impl Tag for Yield {
+    const NAME: &'static str = "yield";
+
+    fn tag(&self, mut args: Args, _language: &Language) -> Result<impl Render> {
+        let name = args.string_literal()?;
+        args.empty()?;
+
+        Ok(RenderFn(name, |name, writer, runtime| {
+            let path = ["content_for".into(), name.clone().into()];
+
+            if let Some(html) = runtime.try_get(&path) {
+                write!(writer, "{}", html.render()).replace("Failed to render")?;
+            }
+            Ok(())
+        }))
+    }
+}
+
+impl Snapshot<'_> {
+    /// The sections rendered by `{% content_for %}`, by name.
+    pub fn sections(&self) -> AHashMap<KString, String> {
+        mem::take(&mut self.runtime().registers().get_mut::<Register>().0)
+    }
+}
+
+#[derive(Default)]
+struct Register(AHashMap<KString, String>);
@@ -0,0 +1,31 @@
+use super::{cache, prelude::*};
+use html_escape::encode_double_quoted_attribute;
+use liquid_core::error::ResultLiquidReplaceExt;
+
+#[derive(Clone)]
+pub struct Csrf;
+
+impl Tag for Csrf {
+    const NAME: &'static str = "csrf";
+
+    fn tag(&self, args: Args, _language: &Language) -> Result<impl Render> {
+        args.empty()?;
+
+        Ok(RenderFn((), |(), writer, runtime| {
+            if cache::is_recording(runtime) {
+                return Error::with_msg("{% csrf %} can't be cached, its token is per user")
+                    .into_err();
+            }
+
+            let token = runtime.get(&["csrf_token".into()])?.to_kstr().into_owned();
+            let token = encode_double_quoted_attribute(&token);
+
+            write!(
+                writer,
+                r#"<input type="hidden" name="_csrf" value="{token}">"#
+            )
+            .replace("Failed to render")?;
+            Ok(())
+        }))
+    }
+}
@@ -0,0 +1,22 @@
+use super::prelude::*;
+use crate::fingerprint::AssetManifest;
+
+#[derive(Clone)]
+pub struct Fingerprint;
+
+impl Filter for Fingerprint {
+    const NAME: &'static str = "fingerprint";
+
+    fn filter(&self, args: FilterArgs) -> Result<impl Apply> {
+        args.empty()?;
+
+        Ok(ApplyFn((), |_, input, _runtime| {
+            let path = input.to_kstr();
+            let url = AssetManifest::get()
+                .url(&path)
+                .unwrap_or_else(|| path.to_string());
+
+            Ok(Value::scalar(url))
+        }))
+    }
+}
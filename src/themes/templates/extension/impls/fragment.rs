@@ -0,0 +1,64 @@
+use super::{cache, prelude::*, Effect};
+use liquid_core::{error::ResultLiquidReplaceExt, Renderable as _};
+use std::mem;
+
+/// Names part of a template, so that it can be rendered on its own for a
+/// request that asks for that fragment. The rest of the template is still
+/// rendered, so tags such as `{% title %}` work wherever they are.
+#[derive(Clone)]
+pub struct Fragment;
+
+impl Block for Fragment {
+    const START: &'static str = "fragment";
+    const END: &'static str = "endfragment";
+
+    fn block(&self, mut args: Args, body: Body, language: &Language) -> Result<impl Render> {
+        let name = args.string_literal()?;
+        let template = body.template(language)?;
+        args.empty()?;
+
+        Ok(RenderFn(
+            (name, template),
+            |(name, template), writer, runtime| {
+                // A cached fragment is kept with the cache entry even when
+                // it isn't wanted, since a later request may want it.
+                if !is_wanted(runtime, name) && !cache::is_recording(runtime) {
+                    return template.render_to(writer, runtime);
+                }
+
+                let mut html = Vec::new();
+                template.render_to(&mut html, runtime)?;
+                writer.write_all(&html).replace("Failed to render")?;
+
+                let html = String::from_utf8(html).replace("Invalid fragment output")?;
+                cache::record(runtime, || Effect::Fragment(name.clone(), html.clone()));
+                keep(runtime, name, html);
+                Ok(())
+            },
+        ))
+    }
+}
+
+/// Whether the template is being rendered for the fragment `name`.
+fn is_wanted(runtime: &dyn Runtime, name: &str) -> bool {
+    Context::get(runtime).is_ok_and(|context| context.fragment.as_deref() == Some(name))
+}
+
+/// Keeps the output of the fragment `name` if it's the one the template is
+/// being rendered for.
+pub(super) fn keep(runtime: &dyn Runtime, name: &str, html: String) {
+    if is_wanted(runtime, name) {
+        runtime.registers().get_mut::<Register>().0 = Some(html);
+    }
+}
+
+impl Snapshot<'_> {
+    /// The output of the fragment the template was rendered for, if the
+    /// template has it.
+    pub fn fragment(&self) -> Option<String> {
+        mem::take(&mut self.runtime().registers().get_mut::<Register>().0)
+    }
+}
+
+#[derive(Default)]
+struct Register(Option<String>);
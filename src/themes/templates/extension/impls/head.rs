@@ -0,0 +1,226 @@
+use super::{
+    cache::{self, Effect},
+    prelude::*,
+};
+use html_escape::encode_double_quoted_attribute;
+use std::{fmt::Write as _, mem};
+
+/// `{% meta "description", page.summary %}` adds a `<meta name>` tag to the
+/// `head` global of the layout.
+#[derive(Clone)]
+pub struct Meta;
+
+impl Tag for Meta {
+    const NAME: &'static str = "meta";
+
+    fn tag(&self, args: Args, language: &Language) -> Result<impl Render> {
+        named(args, language, (Self::NAME, HeadTag::Meta))
+    }
+}
+
+/// `{% og "image", image_url %}` adds an OpenGraph `<meta property="og:*">`
+/// tag to the `head` global of the layout. The name may also be given as
+/// `og:image`. Properties that can have several values, such as `image` and
+/// its `image:width`, are added again each time rather than replaced.
+#[derive(Clone)]
+pub struct Og;
+
+impl Tag for Og {
+    const NAME: &'static str = "og";
+
+    fn tag(&self, args: Args, language: &Language) -> Result<impl Render> {
+        named(args, language, (Self::NAME, HeadTag::og))
+    }
+}
+
+/// `{% canonical url %}` adds a `<link rel="canonical">` tag to the `head`
+/// global of the layout.
+#[derive(Clone)]
+pub struct Canonical;
+
+impl Tag for Canonical {
+    const NAME: &'static str = "canonical";
+
+    fn tag(&self, mut args: Args, language: &Language) -> Result<impl Render> {
+        let expr = args.filter_chain(language)?;
+        args.empty()?;
+
+        Ok(RenderFn(expr, |expr, _, runtime| {
+            let trace = || format!("{{% canonical {expr} %}}").into();
+            let href = expr
+                .evaluate(runtime)
+                .trace_with(trace)?
+                .to_kstr()
+                .into_owned();
+
+            push(runtime, HeadTag::Canonical, href);
+            Ok(())
+        }))
+    }
+}
+
+fn named(
+    mut args: Args,
+    language: &Language,
+    (tag, kind): (&'static str, fn(KString) -> HeadTag),
+) -> Result<impl Render> {
+    let name = args.expression()?;
+    args.comma()?;
+    let content = args.filter_chain(language)?;
+    args.empty()?;
+
+    Ok(RenderFn(
+        (tag, kind, name, content),
+        |(tag, kind, name, content), _, runtime| {
+            let trace = || format!("{{% {tag} {name}, {content} %}}").into();
+            let name = name
+                .evaluate(runtime)
+                .trace_with(trace)?
+                .to_kstr()
+                .into_owned();
+            let content = content.evaluate(runtime).trace_with(trace)?;
+
+            push(runtime, kind(name), content.to_kstr().into_owned());
+            Ok(())
+        },
+    ))
+}
+
+pub(super) fn push(runtime: &dyn Runtime, tag: HeadTag, value: KString) {
+    cache::record(runtime, || Effect::Head(tag.clone(), value.clone()));
+
+    runtime.registers().get_mut::<Register>().push(tag, value);
+}
+
+impl Snapshot<'_> {
+    /// The tags added by `{% meta %}`, `{% og %}` and `{% canonical %}`, one
+    /// per line.
+    pub fn head(&self) -> String {
+        mem::take(&mut *self.runtime().registers().get_mut::<Register>()).into_html()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeadTag {
+    Meta(KString),
+    Og(KString),
+    Canonical,
+}
+
+impl HeadTag {
+    fn og(name: KString) -> Self {
+        match name.strip_prefix("og:") {
+            Some(name) => Self::Og(KString::from_ref(name)),
+            None => Self::Og(name),
+        }
+    }
+
+    /// Whether the tag can be in the head more than once, as OpenGraph
+    /// arrays and their structured properties can.
+    fn repeats(&self) -> bool {
+        let Self::Og(name) = self else {
+            return false;
+        };
+        let (property, _) = name.split_once(':').unwrap_or((name, ""));
+
+        matches!(property, "image" | "video" | "audio") || name == "locale:alternate"
+    }
+}
+
+#[derive(Default)]
+struct Register(Vec<(HeadTag, KString)>);
+
+impl Register {
+    fn push(&mut self, tag: HeadTag, value: KString) {
+        if tag.repeats() {
+            self.0.push((tag, value));
+            return;
+        }
+
+        // A later tag for the same name replaces the earlier one where it was.
+        match self.0.iter_mut().find(|(t, _)| *t == tag) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((tag, value)),
+        }
+    }
+
+    fn into_html(self) -> String {
+        let mut head = String::new();
+
+        for (tag, value) in self.0 {
+            let value = encode_double_quoted_attribute(&value);
+
+            match tag {
+                HeadTag::Meta(name) => {
+                    let name = encode_double_quoted_attribute(&name);
+                    writeln!(head, r#"<meta name="{name}" content="{value}" />"#)
+                }
+                HeadTag::Og(name) => {
+                    let name = encode_double_quoted_attribute(&name);
+                    writeln!(head, r#"<meta property="og:{name}" content="{value}" />"#)
+                }
+                HeadTag::Canonical => writeln!(head, r#"<link rel="canonical" href="{value}" />"#),
+            }
+            .expect("write to string");
+        }
+
+        head
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(name: &'static str) -> HeadTag {
+        HeadTag::Meta(name.into())
+    }
+
+    #[test]
+    fn replaces_in_place() {
+        let mut register = Register::default();
+        register.push(meta("description"), "first".into());
+        register.push(HeadTag::Canonical, "/a".into());
+        register.push(meta("description"), "second".into());
+        register.push(HeadTag::Canonical, "/b".into());
+
+        assert_eq!(
+            register.into_html(),
+            "<meta name=\"description\" content=\"second\" />\n\
+             <link rel=\"canonical\" href=\"/b\" />\n"
+        );
+    }
+
+    #[test]
+    fn escapes_names_and_values() {
+        let mut register = Register::default();
+        register.push(meta("a\"b<c"), "\"><script>".into());
+        register.push(HeadTag::Canonical, "/?q=\"x\"".into());
+
+        assert_eq!(
+            register.into_html(),
+            "<meta name=\"a&quot;b&lt;c\" content=\"&quot;&gt;&lt;script&gt;\" />\n\
+             <link rel=\"canonical\" href=\"/?q=&quot;x&quot;\" />\n"
+        );
+    }
+
+    #[test]
+    fn og_prefix_and_repeats() {
+        let mut register = Register::default();
+        register.push(HeadTag::og("og:title".into()), "One".into());
+        register.push(HeadTag::og("title".into()), "Two".into());
+        register.push(HeadTag::og("og:image".into()), "/a.png".into());
+        register.push(HeadTag::og("image:width".into()), "100".into());
+        register.push(HeadTag::og("image".into()), "/b.png".into());
+        register.push(HeadTag::og("image:width".into()), "200".into());
+
+        assert_eq!(
+            register.into_html(),
+            "<meta property=\"og:title\" content=\"Two\" />\n\
+             <meta property=\"og:image\" content=\"/a.png\" />\n\
+             <meta property=\"og:image:width\" content=\"100\" />\n\
+             <meta property=\"og:image\" content=\"/b.png\" />\n\
+             <meta property=\"og:image:width\" content=\"200\" />\n"
+        );
+    }
+}
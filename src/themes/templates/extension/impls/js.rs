@@ -1,4 +1,7 @@
-use super::prelude::*;
+use super::{
+    cache::{self, Effect},
+    prelude::*,
+};
 use std::mem;
 
 #[derive(Clone)]
@@ -12,12 +15,17 @@ impl Tag for Js {
         args.empty()?;
 
         Ok(RenderFn(js, |js, _, runtime| {
-            runtime.registers().get_mut::<Register>().0.push(js.clone());
+            include(runtime, js.clone());
             Ok(())
         }))
     }
 }
 
+pub(super) fn include(runtime: &dyn Runtime, js: KString) {
+    cache::record(runtime, || Effect::Js(js.clone()));
+    runtime.registers().get_mut::<Register>().0.push(js);
+}
+
 impl Snapshot<'_> {
     pub fn included_scripts(&self) -> Vec<KString> {
         mem::take(&mut self.runtime().registers().get_mut::<Register>().0)
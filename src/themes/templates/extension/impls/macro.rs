@@ -1,8 +1,17 @@
 use super::prelude::*;
+use super::render::{IsolatedFrame, MostlySandboxedStackFrame, WitnessFrame};
 use ahash::AHashMap;
-use std::{str::FromStr, sync::Arc};
-use uuid::Uuid;
+use liquid_core::{model::ScalarCow, Object};
+use std::{cell::RefCell, io, mem, sync::Arc};
 
+/// Defines a macro to `render`, optionally declaring the arguments it takes
+/// as `{% macro card(title, size: "md") %}`. Arguments with a literal
+/// default are optional, and a macro that declares none takes any.
+///
+/// A macro is called by the name written in the tag, as in
+/// `{% render card %}`, and never by the value of a variable, which names a
+/// partial. It can be called anywhere in the page once it's defined, and a
+/// call that follows it in the same template is bound to it when parsing.
 #[derive(Clone)]
 pub struct Macro;
 
@@ -12,75 +21,369 @@ impl Block for Macro {
 
     fn block(&self, mut args: Args, body: Body, language: &Language) -> Result<impl Render> {
         let name = args.identifier()?;
-        let template = Arc::new(body.template(language)?);
-        args.empty()?;
+        let template = body.template(language)?;
+        let params = args.params()?;
+        let r#macro = Arc::new(SavedMacro {
+            name,
+            template,
+            params: (!params.is_empty()).then(|| params.into()),
+        });
+        define(&r#macro);
+
+        Ok(RenderFn(r#macro, |r#macro, _, runtime| {
+            runtime
+                .registers()
+                .get_mut::<SavedMacroMap>()
+                .save(r#macro.name.clone(), r#macro.clone());
 
-        Ok(RenderFn(
-            (name, template),
-            |(name, template), _, runtime| {
-                let key = name.clone();
-                let id = SavedMacroId::new();
-                let r#macro = SavedMacro {
-                    template: template.clone(),
-                };
-
-                runtime.set_global(key, id.to_value());
-                runtime
-                    .registers()
-                    .get_mut::<SavedMacroMap>()
-                    .save(id, r#macro);
-
-                Ok(())
-            },
-        ))
+            Ok(())
+        }))
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct SavedMacro {
-    pub template: Arc<Template>,
+thread_local! {
+    /// The macros defined so far in the template being parsed, by name, or
+    /// `None` for a name defined more than once, which may be either
+    /// depending on how the template renders.
+    static IN_SCOPE: RefCell<Option<AHashMap<KString, Option<Arc<SavedMacro>>>>> =
+        const { RefCell::new(None) };
+}
+
+/// Runs `parse` with a scope of its own for the macros it defines, so that
+/// calls that follow them in the same template can be bound to them.
+pub fn scope<T>(parse: impl FnOnce() -> T) -> T {
+    let outer = IN_SCOPE.replace(Some(AHashMap::new()));
+    let parsed = parse();
+    IN_SCOPE.set(outer);
+    parsed
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct SavedMacroId(Uuid);
+fn define(r#macro: &Arc<SavedMacro>) {
+    IN_SCOPE.with_borrow_mut(|scope| {
+        if let Some(scope) = scope {
+            scope
+                .entry(r#macro.name.clone())
+                .and_modify(|defined| *defined = None)
+                .or_insert_with(|| Some(r#macro.clone()));
+        }
+    });
+}
 
-impl SavedMacroId {
-    const PREFIX: &'static str = "macro-";
+/// The macro `name` names in the template being parsed, if that's certain.
+pub fn in_scope(name: &str) -> Option<Arc<SavedMacro>> {
+    IN_SCOPE.with_borrow(|scope| scope.as_ref()?.get(name)?.clone())
+}
 
-    pub fn new() -> Self {
-        Self(Uuid::new_v4())
+/// The error for a variable that isn't defined but names a macro, which says
+/// how to call it instead.
+pub fn used_as_variable(runtime: &dyn Runtime, path: &[ScalarCow<'_>]) -> Option<Error> {
+    let name = path
+        .iter()
+        .map(|key| key.to_kstr())
+        .collect::<Vec<_>>()
+        .join(".");
+    runtime.registers().get_mut::<SavedMacroMap>().get(&name)?;
+
+    Some(Error::with_msg(format!(
+        "`{name}` is a macro; call it with `{{% render {name} %}}`"
+    )))
+}
+
+/// Makes the macros a partial defines available as `alias.name`, as in
+/// `{% import "ui/macros" as ui %}{% render ui.card, title: "Hi" %}`. Only
+/// the macros are taken from the partial, which is rendered once per page
+/// however often it's imported, and whose output, variables and tags such as
+/// `{% title %}` are discarded.
+#[derive(Clone)]
+pub struct Import;
+
+impl Tag for Import {
+    const NAME: &'static str = "import";
+
+    fn tag(&self, mut args: Args, _language: &Language) -> Result<impl Render> {
+        let name = args.expression()?;
+        args.exact("\"as\" expected.", "as")?;
+        let alias = args.identifier()?;
+        args.empty()?;
+
+        Ok(RenderFn((name, alias), |(name, alias), _, runtime| {
+            let name = name.evaluate(runtime)?.to_kstr().into_owned();
+            let imported = imported(runtime, &name)
+                .trace_with(|| format!("{{% import \"{name}\" as {alias} %}}").into())?;
+
+            let mut saved = runtime.registers().get_mut::<SavedMacroMap>();
+            for (name, r#macro) in imported.iter() {
+                saved.save(format!("{alias}.{name}").into(), r#macro.clone());
+            }
+
+            Ok(())
+        }))
     }
+}
 
-    pub fn to_value(self) -> Value {
-        let string = format!("{}{}", Self::PREFIX, self.0);
-        string.to_value()
+/// The macros the partial `name` defines, which it's rendered for the first
+/// time it's imported in a render.
+fn imported(runtime: &dyn Runtime, name: &str) -> Result<ImportedMacros> {
+    if let Some(macros) = runtime.registers().get_mut::<Imported>().0.get(name) {
+        return Ok(macros.clone());
     }
+
+    let partial = runtime.partials().get(&format!("{name}.liquid"))?;
+    let frame = WitnessFrame::new(IsolatedFrame::new(MostlySandboxedStackFrame::new(
+        runtime,
+        Object::new(),
+    )));
+    if let Ok(context) = Context::get(runtime) {
+        context.install(&frame);
+    }
+
+    partial.render_to(&mut io::sink(), &frame)?;
+
+    let saved = mem::take(&mut frame.registers().get_mut::<SavedMacroMap>().saved);
+    let macros: Arc<[_]> = saved.into_iter().collect();
+    let mut imported = runtime.registers().get_mut::<Imported>();
+    imported.0.insert(KString::from_ref(name), macros.clone());
+
+    Ok(macros)
+}
+
+/// The macros of each partial imported so far in a render.
+#[derive(Default)]
+struct Imported(AHashMap<KString, ImportedMacros>);
+
+/// The macros a partial defines, by name.
+type ImportedMacros = Arc<[(KString, Arc<SavedMacro>)]>;
+
+#[derive(Debug)]
+pub struct SavedMacro {
+    name: KString,
+    pub template: Template,
+    params: Option<Box<Params>>,
 }
 
-impl FromStr for SavedMacroId {
-    type Err = Error;
+/// Declared parameters, with their defaults.
+type Params = [(KString, Option<Value>)];
+
+impl SavedMacro {
+    /// Checks the arguments of a call against the declared parameters, and
+    /// fills in the defaults of those that weren't passed.
+    pub fn bind<'a>(&self, kwargs: &mut EvaluatedKwargs<'a>) -> Result<()> {
+        let Some(params) = &self.params else {
+            return Ok(());
+        };
+        let expected = || {
+            let names = params.iter().map(|(name, _)| name.as_str());
+            names.collect::<Vec<_>>().join(", ")
+        };
+
+        if let Some(key) = kwargs
+            .keys()
+            .find(|key| !params.iter().any(|(name, _)| name == key.as_str()))
+        {
+            return Error::with_msg(format!("Unknown argument {key} for macro {}", self.name))
+                .context("arguments", expected())
+                .into_err();
+        }
+
+        for (name, default) in params.iter() {
+            if kwargs.contains_key(name.as_str()) {
+                continue;
+            }
 
-    fn from_str(s: &str) -> Result<Self> {
-        let err = || Error::with_msg("Invalid macro id");
-        if !s.starts_with(Self::PREFIX) {
-            return Err(err());
+            let Some(default) = default else {
+                return Error::with_msg(format!("Missing argument {name} for macro {}", self.name))
+                    .context("arguments", expected())
+                    .into_err();
+            };
+            kwargs.insert(name.clone().into(), ValueCow::Owned(default.clone()));
         }
-        let uuid = Uuid::parse_str(&s[Self::PREFIX.len()..]).map_err(|_| err())?;
-        Ok(Self(uuid))
+
+        Ok(())
     }
 }
 
+/// The macros defined so far in a render, by the name they're called by.
 #[derive(Debug, Default)]
 pub struct SavedMacroMap {
-    saved: AHashMap<SavedMacroId, SavedMacro>,
+    saved: AHashMap<KString, Arc<SavedMacro>>,
 }
 
 impl SavedMacroMap {
-    fn save(&mut self, id: SavedMacroId, r#macro: SavedMacro) {
-        self.saved.insert(id, r#macro);
+    fn save(&mut self, name: KString, r#macro: Arc<SavedMacro>) {
+        self.saved.insert(name, r#macro);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<SavedMacro>> {
+        self.saved.get(name).cloned()
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes::templates::{desugar, extension::PageFrame, parse, Parser};
+    use liquid_core::{partials::InMemorySource, runtime::RuntimeBuilder, Renderable as _};
+
+    /// Renders `text` with `partials` and `globals`, returning the output and
+    /// the head tags it left.
+    fn render_with(
+        partials: &[(&str, &str)],
+        globals: Object,
+        text: &str,
+    ) -> Result<(String, String)> {
+        let mut source = InMemorySource::new();
+        for (name, text) in partials {
+            source.add(format!("{name}.liquid"), desugar(text).into_owned());
+        }
+
+        let parser = Parser::new(Parser::language(), source).expect("valid partials");
+        let template = parse(&desugar(text), &parser.language)?;
+        let runtime = PageFrame::new(
+            RuntimeBuilder::new()
+                .set_globals(&globals)
+                .set_partials(parser.partials.as_ref())
+                .build(),
+        );
+
+        let mut output = Vec::new();
+        template.render_to(&mut output, &runtime)?;
+        let snapshot = Snapshot {
+            runtime: Box::new(runtime),
+        };
+
+        Ok((String::from_utf8(output).unwrap(), snapshot.head()))
+    }
+
+    fn render(partials: &[(&str, &str)], text: &str) -> Result<(String, String)> {
+        render_with(partials, Object::new(), text)
+    }
+
+    fn card() -> SavedMacro {
+        SavedMacro {
+            name: "card".into(),
+            template: Template::new(Vec::new()),
+            params: Some(Box::new([
+                ("title".into(), None),
+                ("size".into(), Some(Value::scalar("md"))),
+            ])),
+        }
+    }
+
+    #[test]
+    fn binds_arguments() {
+        let mut kwargs = EvaluatedKwargs::default();
+        kwargs.insert("title".into(), ValueCow::Owned(Value::scalar("Hi")));
+        card().bind(&mut kwargs).unwrap();
+        assert_eq!(kwargs["size"].to_kstr(), "md");
+
+        kwargs.insert("size".into(), ValueCow::Owned(Value::scalar("lg")));
+        card().bind(&mut kwargs).unwrap();
+        assert_eq!(kwargs["size"].to_kstr(), "lg");
+
+        let mut kwargs = EvaluatedKwargs::default();
+        let error = card().bind(&mut kwargs).unwrap_err().to_string();
+        assert!(
+            error.contains("Missing argument title for macro card"),
+            "{error}"
+        );
+
+        kwargs.insert("title".into(), ValueCow::Owned(Value::scalar("Hi")));
+        kwargs.insert("colour".into(), ValueCow::Owned(Value::scalar("red")));
+        let error = card().bind(&mut kwargs).unwrap_err().to_string();
+        assert!(
+            error.contains("Unknown argument colour for macro card"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn imports_only_macros() {
+        let ui = r#"{% meta "description", "ui" %}{% assign leaked = "yes" %}
+            {%- macro card(title, size: "md") %}[{{ title }} {{ size }}]{% endmacro %}"#;
+        let text = r#"{% import "ui" as ui %}{% import "ui" as again %}
+            {%- render ui.card, title: "Hi" %}{% render again.card, title: "Yo", size: "lg" %}
+            {%- if leaked %}leaked{% endif %}"#;
+
+        let (output, head) = render(&[("ui", ui)], text).unwrap();
+        assert_eq!(output, "[Hi md][Yo lg]");
+        assert_eq!(head, "");
+
+        let error = render(
+            &[("ui", ui)],
+            r#"{% import "ui" as ui %}{% render ui.card %}"#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("Missing argument title"), "{error}");
+    }
+
+    #[test]
+    fn calls_by_name() {
+        let text = r#"{% for i in (1..3) %}{% macro item(n) %}<{{ n }}>{% endmacro %}
+            {%- render item, n: i %}{% endfor %}"#;
+        assert_eq!(render(&[], text).unwrap().0, "<1><2><3>");
+
+        let text = r#"{% macro card %}card{% endmacro %}{% contain card %}{% endcontain %}"#;
+        assert_eq!(render(&[], text).unwrap().0, "card");
+
+        let text = r#"{% macro card %}card{% endmacro %}{% include card %}"#;
+        let error = render(&[], text).unwrap_err().to_string();
+        assert!(error.contains("Can not `include` macros"), "{error}");
+    }
+
+    #[test]
+    fn binds_calls_when_parsing() {
+        let text =
+            r#"{% if false %}{% macro card %}card{% endmacro %}{% endif %}{% render card %}"#;
+        assert_eq!(render(&[], text).unwrap().0, "card");
+
+        // Which of two macros of the same name is called depends on the render.
+        let text = r#"{% if first %}{% macro card %}A{% endmacro %}
+            {%- else %}{% macro card %}B{% endmacro %}{% endif %}{% render card %}"#;
+        for (first, output) in [(true, "A"), (false, "B")] {
+            let globals = liquid_core::object!({ "first": first });
+            assert_eq!(render_with(&[], globals, text).unwrap().0, output);
+        }
+
+        // A macro in one template isn't in scope in another.
+        let ui = r#"{% if false %}{% macro card %}card{% endmacro %}{% endif %}"#;
+        let error = render(&[("ui", ui)], "{% render card %}")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Unknown variable"), "{error}");
+    }
+
+    #[test]
+    fn macros_are_not_values() {
+        let text = r#"{% macro card %}card{% endmacro %}{{ card }}"#;
+        let error = render(&[], text).unwrap_err().to_string();
+        assert_eq!(
+            error.lines().next(),
+            Some("liquid: `card` is a macro; call it with `{% render card %}`")
+        );
+
+        let ui = r#"{% macro card %}card{% endmacro %}"#;
+        let text = r#"{% import "ui" as ui %}{{ ui.card }}"#;
+        let error = render(&[("ui", ui)], text).unwrap_err().to_string();
+        assert_eq!(
+            error.lines().next(),
+            Some("liquid: `ui.card` is a macro; call it with `{% render ui.card %}`")
+        );
+
+        let error = render(&[], "{{ card }}").unwrap_err().to_string();
+        assert!(error.contains("Unknown variable"), "{error}");
+
+        let marker = "\u{0}\u{1}0:card\u{0}\u{1}";
+        let text = r#"{% macro card %}card{% endmacro %}{% render name %}"#;
+
+        for name in ["card", marker] {
+            let globals = liquid_core::object!({ "name": name });
+            let error = render_with(&[], globals, text).unwrap_err().to_string();
+            assert!(error.contains("Unknown partial-template"), "{error}");
+        }
 
-    pub fn get(&self, id: SavedMacroId) -> Option<SavedMacro> {
-        self.saved.get(&id).cloned()
+        let text = r#"{% macro card %}card{% endmacro %}{{ name }}"#;
+        let globals = liquid_core::object!({ "name": marker });
+        assert_eq!(render_with(&[], globals, text).unwrap().0, marker);
     }
 }
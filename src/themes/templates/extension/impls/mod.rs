@@ -1,18 +1,34 @@
+mod asset_url;
+mod cache;
+mod content_for;
+mod csrf;
 mod default;
+mod fingerprint;
+mod fragment;
+mod head;
 mod js;
 mod r#macro;
 mod render;
 mod title;
+mod translate;
 
+pub use asset_url::AssetUrl;
+pub use cache::{Cache, Effect};
+pub use content_for::{ContentFor, Yield};
+pub use csrf::Csrf;
 pub use default::Default;
+pub use fingerprint::Fingerprint;
+pub use fragment::Fragment;
+pub use head::{Canonical, Meta, Og};
 pub use js::Js;
-pub use r#macro::Macro;
-pub use render::{Contain, Include, Render};
-pub use title::Title;
+pub use r#macro::{scope as macro_scope, Import, Macro};
+pub use render::{Contain, Include, PageFrame, Render};
+pub use title::{Title, TitleSegment};
+pub use translate::Translate;
 
 #[allow(unused)]
 mod prelude {
-    pub use crate::themes::templates::{extension::core::*, Snapshot};
+    pub use crate::themes::templates::{extension::core::*, Context, Snapshot};
     pub use kstring::{KString, KStringCow};
     pub use liquid_core::{
         error::ResultLiquidExt, Error, Expression, Language, Result, Runtime, Template, Value,
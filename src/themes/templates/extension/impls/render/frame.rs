@@ -1,12 +1,27 @@
+use super::super::r#macro::used_as_variable;
 use kstring::{KString, KStringCow, KStringRef};
 use liquid_core::{
     model::{find, try_find, ScalarCow},
     runtime::{PartialStore, Registers},
     Error, Object, ObjectView, Result, Runtime, Value, ValueCow, ValueView,
 };
-use std::{cell::RefCell, collections::BTreeSet, mem};
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeSet,
+    mem,
+};
 
-const NOT_SANDBOXED_VARS: &[&str] = &["current_user", "current_session", "current_theme"];
+const NOT_SANDBOXED_VARS: &[&str] = &[
+    "current_user",
+    "current_session",
+    "current_theme",
+    "theme_settings",
+    "themes",
+    "current_language",
+    "csrf_token",
+    "content_for",
+    "head",
+];
 
 /// A `SandboxedStackFrame`, except it doesn't sandbox registers and
 /// certain global context values.
@@ -21,7 +36,7 @@ impl<P: Runtime, O: ObjectView> MostlySandboxedStackFrame<P, O> {
     }
 
     fn try_get_not_sandboxed(&self, s: &str, path: &[ScalarCow<'_>]) -> Option<ValueCow<'_>> {
-        if NOT_SANDBOXED_VARS.iter().any(|&v| v == s) {
+        if NOT_SANDBOXED_VARS.contains(&s) {
             self.parent.try_get(path)
         } else {
             None
@@ -57,7 +72,11 @@ impl<P: Runtime, O: ObjectView> Runtime for MostlySandboxedStackFrame<P, O> {
             .and_then(|_| try_find(self.data.as_value(), path))
             .map(|v| v.into_owned().into())
             .or_else(|| self.try_get_not_sandboxed(key.as_str(), path))
-            .ok_or_else(|| Error::with_msg("Unknown variable").context("requested variable", key))
+            .ok_or_else(|| {
+                used_as_variable(self, path).unwrap_or_else(|| {
+                    Error::with_msg("Unknown variable").context("requested variable", key)
+                })
+            })
     }
 
     fn set_global(&self, name: KString, val: Value) -> Option<Value> {
@@ -77,6 +96,60 @@ impl<P: Runtime, O: ObjectView> Runtime for MostlySandboxedStackFrame<P, O> {
     }
 }
 
+/// A stack frame with registers of its own, so that what the tags rendered
+/// in it leave there, such as a title or scripts, doesn't reach the page.
+pub struct IsolatedFrame<P> {
+    parent: P,
+    registers: Registers,
+}
+
+impl<P: Runtime> IsolatedFrame<P> {
+    pub fn new(parent: P) -> Self {
+        Self {
+            parent,
+            registers: Registers::default(),
+        }
+    }
+}
+
+impl<P: Runtime> Runtime for IsolatedFrame<P> {
+    fn partials(&self) -> &dyn PartialStore {
+        self.parent.partials()
+    }
+
+    fn name(&self) -> Option<KStringRef<'_>> {
+        self.parent.name()
+    }
+
+    fn roots(&self) -> BTreeSet<KStringCow<'_>> {
+        self.parent.roots()
+    }
+
+    fn try_get(&self, path: &[ScalarCow<'_>]) -> Option<ValueCow<'_>> {
+        self.parent.try_get(path)
+    }
+
+    fn get(&self, path: &[ScalarCow<'_>]) -> Result<ValueCow<'_>> {
+        self.parent.get(path)
+    }
+
+    fn set_global(&self, name: KString, val: Value) -> Option<Value> {
+        self.parent.set_global(name, val)
+    }
+
+    fn set_index(&self, name: KString, val: Value) -> Option<Value> {
+        self.parent.set_index(name, val)
+    }
+
+    fn get_index<'a>(&'a self, name: &str) -> Option<ValueCow<'a>> {
+        self.parent.get_index(name)
+    }
+
+    fn registers(&self) -> &Registers {
+        &self.registers
+    }
+}
+
 type Witnessed<'a> = (KStringCow<'a>, ValueCow<'a>);
 
 /// A stack frame that does not propagate set globals up to the parent,
@@ -158,3 +231,125 @@ impl<P: Runtime> Runtime for WitnessFrame<P> {
         self.parent.registers()
     }
 }
+
+/// A stack frame for the body of a `{% cache %}` block, which notes whether
+/// `csrf_token` was read through it, since output that has the token in it
+/// mustn't be served to anyone else.
+pub struct CacheFrame<P> {
+    parent: P,
+    read_csrf_token: Cell<bool>,
+}
+
+impl<P: Runtime> CacheFrame<P> {
+    pub fn new(parent: P) -> Self {
+        Self {
+            parent,
+            read_csrf_token: Cell::new(false),
+        }
+    }
+
+    pub fn read_csrf_token(&self) -> bool {
+        self.read_csrf_token.get()
+    }
+
+    fn witness(&self, path: &[ScalarCow<'_>]) {
+        if path
+            .first()
+            .is_some_and(|key| key.to_kstr() == "csrf_token")
+        {
+            self.read_csrf_token.set(true);
+        }
+    }
+}
+
+impl<P: Runtime> Runtime for CacheFrame<P> {
+    fn partials(&self) -> &dyn PartialStore {
+        self.parent.partials()
+    }
+
+    fn name(&self) -> Option<KStringRef<'_>> {
+        self.parent.name()
+    }
+
+    fn roots(&self) -> BTreeSet<KStringCow<'_>> {
+        self.parent.roots()
+    }
+
+    fn try_get(&self, path: &[ScalarCow<'_>]) -> Option<ValueCow<'_>> {
+        self.witness(path);
+        self.parent.try_get(path)
+    }
+
+    fn get(&self, path: &[ScalarCow<'_>]) -> Result<ValueCow<'_>> {
+        self.witness(path);
+        self.parent.get(path)
+    }
+
+    fn set_global(&self, name: KString, val: Value) -> Option<Value> {
+        self.parent.set_global(name, val)
+    }
+
+    fn set_index(&self, name: KString, val: Value) -> Option<Value> {
+        self.parent.set_index(name, val)
+    }
+
+    fn get_index<'a>(&'a self, name: &str) -> Option<ValueCow<'a>> {
+        self.parent.get_index(name)
+    }
+
+    fn registers(&self) -> &Registers {
+        self.parent.registers()
+    }
+}
+
+/// The frame a page is rendered in, which only explains what went wrong
+/// when a macro is used as a variable.
+pub struct PageFrame<P> {
+    parent: P,
+}
+
+impl<P: Runtime> PageFrame<P> {
+    pub fn new(parent: P) -> Self {
+        Self { parent }
+    }
+}
+
+impl<P: Runtime> Runtime for PageFrame<P> {
+    fn partials(&self) -> &dyn PartialStore {
+        self.parent.partials()
+    }
+
+    fn name(&self) -> Option<KStringRef<'_>> {
+        self.parent.name()
+    }
+
+    fn roots(&self) -> BTreeSet<KStringCow<'_>> {
+        self.parent.roots()
+    }
+
+    fn try_get(&self, path: &[ScalarCow<'_>]) -> Option<ValueCow<'_>> {
+        self.parent.try_get(path)
+    }
+
+    fn get(&self, path: &[ScalarCow<'_>]) -> Result<ValueCow<'_>> {
+        self.parent
+            .get(path)
+            .map_err(|error| used_as_variable(self, path).unwrap_or(error))
+    }
+
+    fn set_global(&self, name: KString, val: Value) -> Option<Value> {
+        self.parent.set_global(name, val)
+    }
+
+    fn set_index(&self, name: KString, val: Value) -> Option<Value> {
+        self.parent.set_index(name, val)
+    }
+
+    fn get_index<'a>(&'a self, name: &str) -> Option<ValueCow<'a>> {
+        self.parent.get_index(name)
+    }
+
+    fn registers(&self) -> &Registers {
+        self.parent.registers()
+    }
+}
@@ -1,13 +1,13 @@
-use self::{
-    frame::MostlySandboxedStackFrame,
-    output::{Output, OutputHelper},
-};
+use self::output::{Name, Output, OutputHelper};
 use super::prelude::{Render as RenderTrait, *};
 use liquid_core::runtime::{GlobalFrame, StackFrame};
 
 mod frame;
 mod output;
 
+pub use self::frame::PageFrame;
+pub(super) use self::frame::{CacheFrame, IsolatedFrame, MostlySandboxedStackFrame, WitnessFrame};
+
 #[derive(Clone)]
 pub struct Contain;
 
@@ -18,7 +18,7 @@ impl Block for Contain {
     fn block(&self, args: Args, body: Body, language: &Language) -> Result<impl RenderTrait> {
         let (name, kwargs) = name_and_kwargs(args)?;
         let template = body.template(language)?;
-        Ok(Output::<Self>::new(name, kwargs, Some(template)))
+        Output::<Self>::new(name, kwargs, Some(template))
     }
 }
 
@@ -39,7 +39,7 @@ impl Tag for Include {
 
     fn tag(&self, args: Args, _language: &Language) -> Result<impl RenderTrait> {
         let (name, kwargs) = name_and_kwargs(args)?;
-        Ok(Output::<Self>::new(name, kwargs, None))
+        Output::<Self>::new(name, kwargs, None)
     }
 }
 
@@ -60,7 +60,7 @@ impl Tag for Render {
 
     fn tag(&self, args: Args, _language: &Language) -> Result<impl RenderTrait> {
         let (name, kwargs) = name_and_kwargs(args)?;
-        Ok(Output::<Self>::new(name, kwargs, None))
+        Output::<Self>::new(name, kwargs, None)
     }
 }
 
@@ -73,8 +73,8 @@ impl OutputHelper for Render {
     }
 }
 
-fn name_and_kwargs(mut args: Args) -> Result<(Expression, Kwargs)> {
-    let name = args.expression()?;
+fn name_and_kwargs(mut args: Args) -> Result<(Name, Kwargs)> {
+    let name = args.expression_with_path()?;
     if args.comma().is_err() {
         return Ok((name, Kwargs::default()));
     }
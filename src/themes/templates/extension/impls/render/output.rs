@@ -3,33 +3,49 @@ use crate::{
     stuff::STUFF,
     themes::templates::extension::impls::{
         prelude::*,
-        r#macro::{SavedMacroId, SavedMacroMap},
+        r#macro::{self, SavedMacro, SavedMacroMap},
     },
 };
 use liquid_core::Renderable as _;
-use std::{marker::PhantomData, str::FromStr};
+use std::{marker::PhantomData, sync::Arc};
+
+/// The expression naming what to output, with its source if that's a path
+/// that could name a macro.
+pub type Name = (Expression, Option<KString>);
 
 pub struct Output<O> {
-    name: Expression,
+    name: Name,
+    /// The macro the name was bound to when parsing, if it was.
+    bound: Option<Arc<SavedMacro>>,
     kwargs: Kwargs,
     contain: Option<Template>,
     _ty: PhantomData<O>,
 }
 
-impl<O> Output<O> {
-    pub fn new(name: Expression, kwargs: Kwargs, contain: Option<Template>) -> Self {
-        Self {
+impl<O: OutputHelper> Output<O> {
+    pub fn new(name: Name, kwargs: Kwargs, contain: Option<Template>) -> Result<Self> {
+        let bound = name.1.as_deref().and_then(r#macro::in_scope);
+        if bound.is_some() && !O::MACROS {
+            return cannot_output_macros::<O, _>();
+        }
+
+        Ok(Self {
             name,
+            bound,
             kwargs,
             contain,
             _ty: PhantomData,
-        }
+        })
     }
 }
 
 impl<O: OutputHelper> Render for Output<O> {
     fn render(&self, writer: &mut dyn Write, runtime: &dyn Runtime) -> Result<()> {
-        let name = self.name.evaluate(runtime)?.to_kstr().into_owned();
+        let (name, path) = &self.name;
+        let r#macro = self.bound.clone().or_else(|| {
+            let map = runtime.registers().get_mut::<SavedMacroMap>();
+            map.get(path.as_ref()?)
+        });
         let mut kwargs = self.kwargs.evaluate(runtime)?;
         let witness = WitnessFrame::new(runtime);
 
@@ -38,23 +54,20 @@ impl<O: OutputHelper> Render for Output<O> {
             kwargs.extend(witness.witnessed());
         }
 
-        let frame = O::frame(runtime, kwargs);
-
-        match SavedMacroId::from_str(&name) {
-            Ok(macro_id) => {
+        match r#macro {
+            Some(r#macro) => {
                 if !O::MACROS {
-                    return Error::with_msg(format!("Can not `{}` macros", O::TAG)).into_err();
+                    return cannot_output_macros::<O, _>();
                 }
 
-                let r#macro = {
-                    let map = runtime.registers().get_mut::<SavedMacroMap>();
-                    map.get(macro_id)
-                        .ok_or_else(|| Error::with_msg(format!("Unknown macro {}", self.name)))?
-                };
-
+                r#macro.bind(&mut kwargs)?;
+                let frame = O::frame(runtime, kwargs);
                 r#macro.template.render_to(writer, &frame)
             }
-            Err(_) => {
+            None => {
+                let name = name.evaluate(runtime)?;
+                let name = name.to_kstr();
+                let frame = O::frame(runtime, kwargs);
                 let partial = frame.partials().get(&format!("{name}.liquid"))?;
                 let comment = should_write_boundary_comments(runtime);
 
@@ -81,6 +94,10 @@ pub trait OutputHelper: Send + Sync + 'static {
     fn frame<'a>(runtime: &'a dyn Runtime, kwargs: EvaluatedKwargs<'a>) -> impl Runtime + 'a;
 }
 
+fn cannot_output_macros<O: OutputHelper, T>() -> Result<T> {
+    Error::with_msg(format!("Can not `{}` macros", O::TAG)).into_err()
+}
+
 // We can't write comment boundaries for the layout because they would be written before
 // the <!DOCTYPE>, which is invalid.
 fn should_write_boundary_comments(runtime: &dyn Runtime) -> bool {
@@ -1,4 +1,8 @@
-use super::prelude::*;
+use super::{
+    cache::{self, Effect},
+    prelude::*,
+};
+use crate::themes::TitleParts;
 use std::mem;
 
 #[derive(Clone)]
@@ -20,25 +24,67 @@ impl Tag for Title {
                 .to_kstr()
                 .into_owned();
 
-            *runtime.registers().get_mut::<Register>() = Register(Some(title));
+            set_title(runtime, title);
             Ok(())
         }))
     }
 }
 
+/// Adds a segment the page's title is under, such as the section a partial
+/// renders, so nested partials build up a breadcrumb.
+#[derive(Clone)]
+pub struct TitleSegment;
+
+impl Tag for TitleSegment {
+    const NAME: &'static str = "title_segment";
+
+    fn tag(&self, mut args: Args, language: &Language) -> Result<impl Render> {
+        let expr = args.filter_chain(language)?;
+        args.empty()?;
+
+        Ok(RenderFn(expr, |expr, _, runtime| {
+            let trace = || format!("{{% title_segment {expr} %}}").into();
+            let segment = expr
+                .evaluate(runtime)
+                .trace_with(trace)?
+                .into_owned()
+                .to_kstr()
+                .into_owned();
+
+            push_segment(runtime, segment);
+            Ok(())
+        }))
+    }
+}
+
+pub(super) fn set_title(runtime: &dyn Runtime, title: KString) {
+    cache::record(runtime, || Effect::Title(title.clone()));
+    runtime.registers().get_mut::<Register>().title = Some(title);
+}
+
+pub(super) fn push_segment(runtime: &dyn Runtime, segment: KString) {
+    cache::record(runtime, || Effect::TitleSegment(segment.clone()));
+    runtime
+        .registers()
+        .get_mut::<Register>()
+        .segments
+        .push(segment);
+}
+
 impl Snapshot<'_> {
-    pub fn title(&self, base: Option<&str>) -> Option<KString> {
+    pub fn title_parts(&self, base: Option<&str>) -> TitleParts {
         let mut reg = self.runtime().registers().get_mut::<Register>();
-        let title = mem::take(&mut reg.0);
 
-        match (title, base) {
-            (Some(title), Some(base)) => Some(format!("{title} • {base}").into()),
-            (Some(title), None) => Some(title),
-            (None, Some(base)) => Some(base.to_string().into()),
-            (None, None) => None,
+        TitleParts {
+            title: reg.title.take(),
+            segments: mem::take(&mut reg.segments),
+            base: base.map(KString::from_ref),
         }
     }
 }
 
 #[derive(Default)]
-struct Register(Option<KString>);
+struct Register {
+    title: Option<KString>,
+    segments: Vec<KString>,
+}
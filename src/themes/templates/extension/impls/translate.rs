@@ -0,0 +1,31 @@
+use super::prelude::*;
+use liquid_core::Object;
+
+#[derive(Clone)]
+pub struct Translate;
+
+impl Filter for Translate {
+    const NAME: &'static str = "t";
+
+    fn filter(&self, mut args: FilterArgs) -> Result<impl Apply> {
+        let kwargs = args.kwargs();
+        args.empty()?;
+
+        Ok(ApplyFn(kwargs, |kwargs, input, runtime| {
+            let key = input.to_kstr();
+            let args = kwargs
+                .evaluate(runtime)?
+                .into_iter()
+                .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                .collect::<Object>();
+
+            let Context {
+                languages,
+                language,
+                ..
+            } = Context::get(runtime)?;
+
+            Ok(Value::scalar(languages.t(&language, &key, &args)))
+        }))
+    }
+}
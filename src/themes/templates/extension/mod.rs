@@ -5,15 +5,32 @@ use liquid_core::Language;
 mod core;
 mod impls;
 
+pub(crate) use self::impls::Effect;
+pub(super) use self::impls::{macro_scope, PageFrame};
+
 pub fn extension(language: &mut Language) {
     language
         // Blocks
+        .block(Ex(Cache))
         .block(Ex(Contain))
+        .block(Ex(ContentFor))
+        .block(Ex(Fragment))
         .block(Ex(Macro))
         // Tags
+        .tag(Ex(Canonical))
+        .tag(Ex(Csrf))
         .tag(Ex(Default))
+        .tag(Ex(Import))
         .tag(Ex(Js))
+        .tag(Ex(Meta))
+        .tag(Ex(Og))
         .tag(Ex(Include))
         .tag(Ex(Render))
-        .tag(Ex(Title));
+        .tag(Ex(Title))
+        .tag(Ex(TitleSegment))
+        .tag(Ex(Yield))
+        // Filters
+        .filter(Ex(AssetUrl))
+        .filter(Ex(Fingerprint))
+        .filter(Ex(Translate));
 }
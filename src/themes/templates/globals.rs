@@ -1,31 +1,74 @@
+use super::Context;
 use crate::{
+    fingerprint::AssetManifest,
+    languages::Languages,
+    scratch,
     serve::{CurrentHooks, CurrentState},
     stuff::STUFF,
-    themes::Theme,
+    themes::{Theme, Themes, TitleParts},
 };
+use ahash::AHashMap;
 use anyhow::Error;
+use axum::http::StatusCode;
 use kstring::KString;
-use liquid::{Object, ObjectView};
+use liquid::{model::Value, Object, ObjectView};
 use serde::Serialize;
 use std::time::SystemTime;
 
 #[derive(Debug)]
-pub struct Globals(Object);
+pub struct Globals {
+    object: Object,
+    context: Option<Context>,
+}
 
 impl Globals {
+    fn new(object: Object) -> Self {
+        Self {
+            object,
+            context: None,
+        }
+    }
+
     pub fn as_object_view(&self) -> &dyn ObjectView {
-        &self.0
+        &self.object
+    }
+
+    pub fn context(&self) -> Option<&Context> {
+        self.context.as_ref()
+    }
+
+    /// Renders for the named `{% fragment %}`, so it can be taken from the
+    /// snapshot afterwards.
+    pub fn with_fragment(mut self, name: Option<&str>) -> Self {
+        if let Some(context) = self.context.as_mut() {
+            context.fragment = name.map(KString::from_ref);
+        }
+        self
     }
 
     fn insert(&mut self, key: impl Into<KString>, value: impl Serialize) {
         let value = liquid::model::to_value(&value).expect("invalid global");
-        self.0.insert(key.into(), value);
+        self.object.insert(key.into(), value);
     }
 
     fn insert_shared<C: CurrentHooks>(&mut self, shared: SharedGlobals<C>) {
+        self.context = Some(Context {
+            languages: shared.languages.clone(),
+            language: KString::from_ref(shared.current.language.tag()),
+            theme: shared.theme.slug().clone(),
+            theme_assets: shared.theme.asset_hashes.clone(),
+            template: KString::from_ref(shared.template),
+            cache: shared.themes.fragments.clone(),
+            fragment: None,
+        });
+
         self.insert("current_user", shared.current.user.get());
         self.insert("current_session", shared.current.session.get());
+        self.insert("current_language", shared.current.language.get());
+        self.insert("csrf_token", shared.current.csrf.token());
         self.insert("current_theme", shared.theme);
+        self.insert("theme_settings", shared.themes.settings(shared.theme));
+        self.insert("themes", shared.themes.listing().as_slice());
         self.insert("template", shared.template);
     }
 }
@@ -33,7 +76,9 @@ impl Globals {
 pub struct SharedGlobals<'a, C: CurrentHooks> {
     pub current: &'a CurrentState<C>,
     pub theme: &'a Theme,
+    pub themes: &'a Themes,
     pub template: &'a str,
+    pub languages: &'a Languages,
 }
 
 pub struct TemplateGlobals<'a, C: CurrentHooks> {
@@ -43,7 +88,7 @@ pub struct TemplateGlobals<'a, C: CurrentHooks> {
 
 impl<C: CurrentHooks> From<TemplateGlobals<'_, C>> for Globals {
     fn from(globals: TemplateGlobals<'_, C>) -> Self {
-        let mut this = Self(globals.props);
+        let mut this = Self::new(globals.props);
 
         this.insert_shared(globals.shared);
         this
@@ -53,18 +98,29 @@ impl<C: CurrentHooks> From<TemplateGlobals<'_, C>> for Globals {
 pub struct LayoutGlobals<'a, C: CurrentHooks> {
     pub shared: SharedGlobals<'a, C>,
     pub title: Option<&'a str>,
-    pub content: &'a str,
+    pub title_parts: &'a TitleParts,
+    /// Moved into the globals rather than copied, since it's the bulk of the
+    /// page.
+    pub content: String,
+    pub sections: &'a AHashMap<KString, String>,
+    pub head: &'a str,
     pub scripts: &'a [KString],
 }
 
 impl<C: CurrentHooks> From<LayoutGlobals<'_, C>> for Globals {
     fn from(globals: LayoutGlobals<'_, C>) -> Self {
-        let mut this = Self(Object::new());
+        let mut this = Self::new(Object::new());
 
+        this.insert("stylesheet_url", stylesheet_url(globals.shared.theme));
         this.insert_shared(globals.shared);
         this.insert("title", globals.title);
-        this.insert("content", globals.content);
+        this.insert("title_parts", globals.title_parts);
+        this.object
+            .insert("content".into(), Value::scalar(globals.content));
+        this.insert("content_for", globals.sections);
+        this.insert("head", globals.head);
         this.insert("scripts", globals.scripts);
+        this.insert("script_urls", script_urls(globals.scripts));
         this.insert("cache_buster", cache_buster());
         this.insert("is_layout", true);
         this.insert("reload", STUFF.reload);
@@ -74,30 +130,36 @@ impl<C: CurrentHooks> From<LayoutGlobals<'_, C>> for Globals {
 
 pub struct ErrorGlobals<'a, C: CurrentHooks> {
     pub shared: SharedGlobals<'a, C>,
-    pub error: &'a Error,
+    pub status: StatusCode,
+    pub error: Option<&'a Error>,
 }
 
 impl<C: CurrentHooks> From<ErrorGlobals<'_, C>> for Globals {
     fn from(globals: ErrorGlobals<'_, C>) -> Self {
-        let mut this = Self(Object::new());
+        let mut this = Self::new(Object::new());
 
         this.insert_shared(globals.shared);
-        this.insert("error", format!("{:?}", globals.error));
+        this.insert("status", globals.status.as_u16());
+        this.insert("error", globals.error.map(|error| format!("{error:?}")));
         this
     }
 }
 
-pub struct NotFoundGlobals<'a, C: CurrentHooks> {
-    pub shared: SharedGlobals<'a, C>,
+fn stylesheet_url(theme: &Theme) -> Option<String> {
+    theme
+        .has_tailwind()
+        .then(|| fingerprinted(scratch::tailwind_output_url(theme.slug())))
 }
 
-impl<C: CurrentHooks> From<NotFoundGlobals<'_, C>> for Globals {
-    fn from(globals: NotFoundGlobals<'_, C>) -> Self {
-        let mut this = Self(Object::new());
+fn script_urls(scripts: &[KString]) -> Vec<String> {
+    scripts
+        .iter()
+        .map(|script| fingerprinted(scratch::esbuild_output_url(script)))
+        .collect()
+}
 
-        this.insert_shared(globals.shared);
-        this
-    }
+fn fingerprinted(url: String) -> String {
+    AssetManifest::get().url(&url).unwrap_or(url)
 }
 
 fn cache_buster() -> u64 {
@@ -1,52 +1,65 @@
 use anyhow::Result;
 use liquid::model::ScalarCow;
 use liquid_core::{runtime, Renderable, Value};
-use std::{fmt, sync::Arc};
+use std::{fmt, io::Write, sync::Arc};
 
+mod context;
 mod extension;
 mod globals;
 mod parser;
 
-pub use globals::{
-    ErrorGlobals, Globals, LayoutGlobals, NotFoundGlobals, SharedGlobals, TemplateGlobals,
-};
-pub use parser::Parser;
+use self::extension::PageFrame;
+
+pub use context::Context;
+pub use globals::{ErrorGlobals, Globals, LayoutGlobals, SharedGlobals, TemplateGlobals};
+pub use parser::{desugar, parse, Parser};
+
+pub(crate) use self::extension::Effect;
 
 const BASE: &str = r#"{% include template %}"#;
 
+#[derive(Clone)]
 pub struct Templates {
-    template: runtime::Template,
+    template: Arc<runtime::Template>,
     partials: Arc<dyn runtime::PartialStore + Send + Sync>,
 }
 
 impl Templates {
     pub fn new(parser: &Parser) -> Self {
-        let render = liquid_core::parser::parse(BASE, &parser.language).expect("invalid BASE");
-        let template = runtime::Template::new(render);
+        let template = Arc::new(parser::parse(BASE, &parser.language).expect("invalid BASE"));
         let partials = parser.partials.clone();
 
         Self { template, partials }
     }
 
-    pub fn render(&self, globals: &Globals) -> Result<String> {
-        self.render_with_snapshot(globals).map(|v| v.0)
+    pub fn render(&self, globals: &Globals, writer: &mut dyn Write) -> Result<()> {
+        self.render_with_snapshot(globals, writer)?;
+        Ok(())
     }
 
+    /// Renders into `writer`, returning what the template left in the
+    /// runtime, such as its title and scripts.
     pub fn render_with_snapshot<'a>(
         &'a self,
         globals: &'a Globals,
-    ) -> Result<(String, Snapshot<'a>)> {
-        let runtime = runtime::RuntimeBuilder::new()
-            .set_globals(globals.as_object_view())
-            .set_partials(self.partials.as_ref())
-            .build();
-
-        let html = self.template.render(&runtime)?;
-        let snapshot = Snapshot {
-            runtime: Box::new(runtime),
-        };
+        writer: &mut dyn Write,
+    ) -> Result<Snapshot<'a>> {
+        let runtime = PageFrame::new(
+            runtime::RuntimeBuilder::new()
+                .set_globals(globals.as_object_view())
+                .set_partials(self.partials.as_ref())
+                .build(),
+        );
 
-        Ok((html, snapshot))
+        if let Some(context) = globals.context() {
+            context.clone().install(&runtime);
+        }
+
+        self.template.render_to(writer, &runtime)?;
+
+        Ok(Snapshot {
+            runtime: Box::new(runtime),
+        })
     }
 }
 
@@ -1,29 +1,177 @@
-use super::extension::extension;
+use super::extension::{extension, macro_scope};
+use ahash::AHashMap;
 use anyhow::Result;
-use liquid::partials::PartialCompiler;
-use liquid_core::{runtime, Language, ParseBlock, ParseFilter, ParseTag};
+use liquid::partials::{InMemorySource, PartialSource};
+use liquid_core::{
+    runtime::{self, PartialStore},
+    Error, Language, ParseBlock, ParseFilter, ParseTag,
+};
 use liquid_lib::stdlib;
-use std::sync::Arc;
+use std::{borrow::Cow, fmt, sync::Arc};
 
 pub struct Parser {
     pub language: Arc<Language>,
-    pub partials: Arc<dyn runtime::PartialStore + Send + Sync>,
+    pub partials: Arc<dyn PartialStore + Send + Sync>,
 }
 
 impl Parser {
-    pub fn new(compiler: impl PartialCompiler) -> Result<Self> {
+    /// Liquid with the tags, blocks and filters themes can use.
+    pub fn language() -> Arc<Language> {
         let mut language = Language::empty();
 
         stdlib(&mut language);
         extension(&mut language);
 
-        let language = Arc::new(language);
-        let partials = compiler.compile(language.clone())?.into();
+        Arc::new(language)
+    }
+
+    /// Parses each partial as a template of its own, see [`parse`]. A
+    /// partial that fails to parse is an error only when it's rendered.
+    pub fn new(language: Arc<Language>, source: InMemorySource) -> Result<Self> {
+        let partials = source
+            .names()
+            .into_iter()
+            .map(|name| {
+                let template = source.get(name).and_then(|text| parse(&text, &language));
+                let template = template.map(|t| Arc::new(t) as Arc<dyn runtime::Renderable>);
+                (name.to_owned(), template)
+            })
+            .collect();
+        let partials = Arc::new(Partials(partials));
 
         Ok(Self { language, partials })
     }
 }
 
+/// Parses a template, binding each call of a macro that the template defines
+/// before it to that macro.
+pub fn parse(text: &str, language: &Language) -> liquid_core::Result<runtime::Template> {
+    macro_scope(|| liquid_core::parser::parse(text, language)).map(runtime::Template::new)
+}
+
+type Partial = liquid_core::Result<Arc<dyn runtime::Renderable>>;
+
+/// The parsed partials, by name.
+struct Partials(AHashMap<String, Partial>);
+
+impl PartialStore for Partials {
+    fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    fn names(&self) -> Vec<&str> {
+        self.0.keys().map(String::as_str).collect()
+    }
+
+    fn try_get(&self, name: &str) -> Option<Arc<dyn runtime::Renderable>> {
+        self.0.get(name)?.clone().ok()
+    }
+
+    fn get(&self, name: &str) -> liquid_core::Result<Arc<dyn runtime::Renderable>> {
+        let Some(partial) = self.0.get(name) else {
+            let mut available = self.names();
+            available.sort_unstable();
+
+            return Err(Error::with_msg("Unknown partial-template")
+                .context("requested partial", name.to_owned())
+                .context("available partials", available.join(", ")));
+        };
+        partial.clone()
+    }
+}
+
+impl fmt::Debug for Partials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.names().fmt(f)
+    }
+}
+
+/// Rewrites `{% macro name(params) %}` as `{% macro name params %}`, which
+/// Liquid can tokenize. The parentheses become spaces so that positions in
+/// errors still match the source. Nothing in `raw` and `comment` blocks is
+/// rewritten.
+pub fn desugar(text: &str) -> Cow<'_, str> {
+    let mut desugared = Cow::Borrowed(text);
+    // The block being skipped, with how deeply comments are nested in it.
+    let mut skipping: Option<(&str, usize)> = None;
+
+    for (start, _) in text.match_indices("{%") {
+        let rest = text[start + 2..].trim_start_matches('-').trim_start();
+        let name = tag_name(rest);
+
+        match &mut skipping {
+            Some((block, depth)) => {
+                if name.strip_prefix("end") == Some(*block) {
+                    *depth -= 1;
+                } else if name == "comment" && *block == "comment" {
+                    *depth += 1;
+                }
+
+                if *depth == 0 {
+                    skipping = None;
+                }
+                continue;
+            }
+            None if name == "raw" || name == "comment" => {
+                skipping = Some((name, 1));
+                continue;
+            }
+            None => {}
+        }
+
+        let Some(rest) = rest
+            .strip_prefix("macro")
+            .filter(|r| r.starts_with(char::is_whitespace))
+        else {
+            continue;
+        };
+
+        let rest = rest
+            .trim_start()
+            .trim_start_matches(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            .trim_start();
+        let Some(close) = rest.strip_prefix('(').and_then(closing_paren) else {
+            continue;
+        };
+        let open = text.len() - rest.len();
+        let close = open + 1 + close;
+
+        let desugared = desugared.to_mut();
+        desugared.replace_range(open..open + 1, " ");
+        desugared.replace_range(close..close + 1, " ");
+    }
+
+    desugared
+}
+
+/// The name of the tag whose markup starts `rest`.
+fn tag_name(rest: &str) -> &str {
+    let end = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+
+    &rest[..end]
+}
+
+/// The offset of the `)` that ends a parameter list, skipping over quoted
+/// strings, unless the tag ends first.
+fn closing_paren(params: &str) -> Option<usize> {
+    let mut quote = None;
+
+    for (i, c) in params.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, ')') => return Some(i),
+            (None, '%') if params[i..].starts_with("%}") => return None,
+            (None, _) => {}
+        }
+    }
+
+    None
+}
+
 fn stdlib(language: &mut Language) {
     language
         .tag(stdlib::AssignTag)
@@ -118,3 +266,41 @@ impl LanguageExt for Language {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn desugars_macro_params() {
+        let text = r#"{% macro card(title, label: ")", size: 'md') %}{{ title }}{% endmacro %}"#;
+        let desugared = desugar(text);
+
+        assert_eq!(
+            desugared,
+            r#"{% macro card title, label: ")", size: 'md'  %}{{ title }}{% endmacro %}"#
+        );
+        assert_eq!(desugared.len(), text.len());
+
+        assert_eq!(desugar("{%- macro card (a) -%}"), "{%- macro card  a  -%}");
+        assert!(matches!(
+            desugar("{% macro card %}(a){% endmacro %}"),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(desugar("{% macro card(a %}"), Cow::Borrowed(_)));
+        assert!(matches!(desugar("{% render card(a) %}"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn leaves_raw_and_comments() {
+        let text = "{% raw %}{% macro a(b) %}{% endraw %}\
+                    {%- comment -%}{% comment %}{% endcomment %}{% macro a(b) %}{% endcomment %}";
+        assert!(matches!(desugar(text), Cow::Borrowed(_)));
+
+        let text = "{% raw %}{% raw %}{% endraw %}{% macro a(b) %}{% endmacro %}";
+        assert_eq!(
+            desugar(text),
+            "{% raw %}{% raw %}{% endraw %}{% macro a b  %}{% endmacro %}"
+        );
+    }
+}
@@ -1,14 +1,21 @@
-use super::templates::*;
+use super::{
+    assets::{AssetDir, AssetHashes},
+    page::{Chunks, Page},
+    templates::*,
+    title::{TitleFormat, TitleParts},
+};
 use crate::{
     scratch,
-    serve::{CurrentHooks, CurrentState},
+    serve::{Application, CurrentHooks, CurrentState},
     stuff::STUFF,
 };
 use anyhow::{Error, Result};
+use axum::http::StatusCode;
 use camino::Utf8PathBuf;
 use kstring::KString;
 use liquid::Object;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Serialize)]
 pub struct Theme {
@@ -16,16 +23,35 @@ pub struct Theme {
     #[serde(flatten)]
     pub(super) manifest: ThemeManifest,
     #[serde(skip)]
+    pub(super) lineage: Box<[KString]>,
+    #[serde(skip)]
+    pub(super) assets: Box<[AssetDir]>,
+    #[serde(skip)]
+    pub(super) asset_hashes: AssetHashes,
+    #[serde(skip)]
     pub(super) templates: Templates,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ThemeManifest {
     pub name: KString,
+    #[serde(default)]
+    pub parent: Option<KString>,
     pub layout: KString,
     pub error: KString,
     pub not_found: KString,
-    pub tailwind: ThemeManifestTailwind,
+    /// The stylesheet tailwind compiles, if the theme has one.
+    #[serde(default)]
+    pub tailwind: Option<ThemeManifestTailwind>,
+    /// Templates for error statuses other than 404 and 500, keyed by the
+    /// status code, such as `403 = "_errors/forbidden"`.
+    #[serde(default)]
+    pub statuses: BTreeMap<KString, KString>,
+    /// Replaces the application's title format.
+    #[serde(default)]
+    pub title: Option<TitleFormat>,
+    #[serde(default, skip_serializing)]
+    pub settings: toml::Table,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -43,102 +69,207 @@ impl Theme {
         &self.manifest.name
     }
 
+    /// The slugs of the themes this one inherits from, nearest first.
+    pub fn ancestors(&self) -> &[KString] {
+        &self.lineage
+    }
+
     pub fn dir(&self) -> Utf8PathBuf {
         STUFF.themes.dir.join(self.slug.as_str())
     }
 
-    pub fn tailwind_input_path(&self) -> Utf8PathBuf {
-        self.dir().join(self.manifest.tailwind.input.as_str())
+    /// Resolves a path relative to this theme, or to the nearest ancestor
+    /// that has it if this theme doesn't.
+    pub fn inherited_path(&self, path: &str) -> Utf8PathBuf {
+        let own = self.dir().join(path);
+
+        if own.exists() {
+            return own;
+        }
+
+        self.lineage
+            .iter()
+            .map(|slug| STUFF.themes.dir.join(slug.as_str()).join(path))
+            .find(|path| path.exists())
+            .unwrap_or(own)
+    }
+
+    pub fn has_tailwind(&self) -> bool {
+        self.manifest.tailwind.is_some()
+    }
+
+    pub fn tailwind_input_path(&self) -> Option<Utf8PathBuf> {
+        let tailwind = self.manifest.tailwind.as_ref()?;
+        Some(self.inherited_path(&tailwind.input))
     }
 
     pub fn tailwind_output_path(&self) -> Utf8PathBuf {
         scratch::tailwind_output_path(self.slug.as_str())
     }
 
-    pub fn tailwind_config_path(&self) -> Utf8PathBuf {
-        self.dir().join(self.manifest.tailwind.config.as_str())
+    pub fn tailwind_config_path(&self) -> Option<Utf8PathBuf> {
+        let tailwind = self.manifest.tailwind.as_ref()?;
+        Some(self.inherited_path(&tailwind.config))
     }
 
     pub fn render<C: CurrentHooks>(
         &self,
         template: &str,
-        base_title: Option<&str>,
+        app: &impl Application,
         props: Object,
         current: &CurrentState<C>,
-    ) -> Result<String> {
-        let shared = self.shared_globals(template, current);
+    ) -> Result<Page> {
+        let shared = self.shared_globals(template, app, current);
         let globals = TemplateGlobals { shared, props };
-        self.render_inner(base_title, globals, current)
+        self.render_inner(app, globals, current)
+    }
+
+    /// Renders a template without the layout, or only the named fragment of
+    /// it if the template has one.
+    pub fn render_fragment<C: CurrentHooks>(
+        &self,
+        template: &str,
+        fragment: Option<&str>,
+        app: &impl Application,
+        props: Object,
+        current: &CurrentState<C>,
+    ) -> Result<Page> {
+        let shared = self.shared_globals(template, app, current);
+        let globals = Globals::from(TemplateGlobals { shared, props }).with_fragment(fragment);
+
+        let mut content = Chunks::default();
+        let snapshot = self
+            .templates
+            .render_with_snapshot(&globals, &mut content)?;
+        let (title, _) = self.title(&snapshot, app);
+
+        let page = match snapshot.fragment() {
+            Some(html) => Page::whole(html),
+            None => Page::new(content),
+        };
+        Ok(page.with_title(title))
     }
 
     pub fn render_error<C: CurrentHooks>(
         &self,
         error: &Error,
-        base_title: Option<&str>,
+        app: &impl Application,
         current: &CurrentState<C>,
-    ) -> Result<String> {
-        let template = &self.manifest.error;
-        let shared = self.shared_globals(template, current);
-        let globals = ErrorGlobals { shared, error };
-        self.render_inner(base_title, globals, current)
+    ) -> Result<Page> {
+        self.render_status(StatusCode::INTERNAL_SERVER_ERROR, Some(error), app, current)
     }
 
     pub fn render_not_found<C: CurrentHooks>(
         &self,
-        base_title: Option<&str>,
+        app: &impl Application,
+        current: &CurrentState<C>,
+    ) -> Result<Page> {
+        self.render_status(StatusCode::NOT_FOUND, None, app, current)
+    }
+
+    /// Renders the page for an error status, with the error if there is one.
+    pub fn render_status<C: CurrentHooks>(
+        &self,
+        status: StatusCode,
+        error: Option<&Error>,
+        app: &impl Application,
         current: &CurrentState<C>,
-    ) -> Result<String> {
-        let template = &self.manifest.not_found;
-        let shared = self.shared_globals(template, current);
-        let globals = NotFoundGlobals { shared };
-        self.render_inner(base_title, globals, current)
+    ) -> Result<Page> {
+        let template = self.status_template(status);
+        let shared = self.shared_globals(template, app, current);
+        let globals = ErrorGlobals {
+            shared,
+            status,
+            error,
+        };
+        self.render_inner(app, globals, current)
+    }
+
+    /// The template the manifest maps a status to, or else `not_found` for
+    /// 404 and `error` for anything else.
+    pub fn status_template(&self, status: StatusCode) -> &str {
+        match self.manifest.statuses.get(status.as_str()) {
+            Some(template) => template,
+            None if status == StatusCode::NOT_FOUND => &self.manifest.not_found,
+            None => &self.manifest.error,
+        }
     }
 
     fn render_inner<C: CurrentHooks>(
         &self,
-        base_title: Option<&str>,
+        app: &impl Application,
         globals: impl Into<Globals>,
         current: &CurrentState<C>,
-    ) -> Result<String> {
+    ) -> Result<Page> {
         let globals = globals.into();
-        let (content, snapshot) = self.templates.render_with_snapshot(&globals)?;
-        self.render_layout(&content, base_title, snapshot, current)
+        let mut content = Vec::new();
+        let snapshot = self
+            .templates
+            .render_with_snapshot(&globals, &mut content)?;
+        let content = String::from_utf8(content)?;
+        self.render_layout(content, app, snapshot, current)
     }
 
+    /// Renders the layout with the content, once the content has set the
+    /// page's title, scripts and sections. The layout is sent as it renders,
+    /// so a large page starts arriving before it's done.
     fn render_layout<C: CurrentHooks>(
         &self,
-        content: &str,
-        base_title: Option<&str>,
+        content: String,
+        app: &impl Application,
         snapshot: Snapshot,
         current: &CurrentState<C>,
-    ) -> Result<String> {
+    ) -> Result<Page> {
         let template = &self.manifest.layout;
         let mut scripts = STUFF.scripts.autoload.to_vec();
-        let title = snapshot.title(base_title);
+        let (title, title_parts) = self.title(&snapshot, app);
+        let sections = snapshot.sections();
+        let head = snapshot.head();
 
         scripts.extend(snapshot.included_scripts());
 
-        let shared = self.shared_globals(template, current);
+        let shared = self.shared_globals(template, app, current);
         let globals = LayoutGlobals {
             shared,
             title: title.as_deref(),
+            title_parts: &title_parts,
             content,
+            sections: &sections,
+            head: &head,
             scripts: &scripts,
         }
         .into();
 
-        self.templates.render(&globals)
+        let templates = self.templates.clone();
+        Ok(Page::stream(move |writer| {
+            templates.render(&globals, writer)
+        }))
+    }
+
+    /// The page's title as the theme's or application's format puts it
+    /// together, and the parts it's made of.
+    fn title(&self, snapshot: &Snapshot, app: &impl Application) -> (Option<KString>, TitleParts) {
+        let parts = snapshot.title_parts(app.base_page_title());
+        let title = match self.manifest.title.as_ref().or(app.title_format()) {
+            Some(format) => format.apply(&parts),
+            None => TitleFormat::DEFAULT.apply(&parts),
+        };
+
+        (title, parts)
     }
 
     fn shared_globals<'a, C: CurrentHooks>(
         &'a self,
         template: &'a str,
+        app: &'a impl Application,
         current: &'a CurrentState<C>,
     ) -> SharedGlobals<'a, C> {
         SharedGlobals {
             current,
             theme: self,
+            themes: app.themes(),
             template,
+            languages: app.languages(),
         }
     }
 }
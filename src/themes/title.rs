@@ -0,0 +1,135 @@
+use kstring::KString;
+use serde::{Deserialize, Serialize};
+
+/// How a page's title is put together with the base title, from `[title]`
+/// in a theme manifest or from
+/// [`Application::title_format`](crate::serve::Application::title_format).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TitleFormat {
+    /// Where the page's title and the base title go, as `{title}` and
+    /// `{base}`. Only used when there's both, and the default is used instead
+    /// of a format without `{title}`.
+    pub format: KString,
+    /// Joins a page's title and its segments, most specific first.
+    pub separator: KString,
+}
+
+impl TitleFormat {
+    pub const DEFAULT: Self = Self {
+        format: KString::from_static("{title} • {base}"),
+        separator: KString::from_static(" • "),
+    };
+
+    pub fn apply(&self, parts: &TitleParts) -> Option<KString> {
+        let mut page = parts.segments.iter().chain(&parts.title).rev();
+        let title = page.next().map(|first| {
+            page.fold(first.to_string(), |mut title, segment| {
+                title.push_str(&self.separator);
+                title.push_str(segment);
+                title
+            })
+        });
+
+        match (title, &parts.base) {
+            (Some(title), Some(base)) => Some(self.substitute(&title, base).into()),
+            (Some(title), None) => Some(title.into()),
+            (None, Some(base)) => Some(base.clone()),
+            (None, None) => None,
+        }
+    }
+
+    /// Puts `title` and `base` in the format in one pass, so placeholders in
+    /// them are left alone.
+    fn substitute(&self, title: &str, base: &str) -> String {
+        let format = match self.format.contains("{title}") {
+            true => &self.format,
+            false => &Self::DEFAULT.format,
+        };
+        let mut rest = format.as_str();
+        let mut out = String::with_capacity(format.len() + title.len() + base.len());
+
+        while let Some(at) = rest.find('{') {
+            out.push_str(&rest[..at]);
+            rest = &rest[at..];
+
+            let (value, len) = if rest.starts_with("{title}") {
+                (title, "{title}".len())
+            } else if rest.starts_with("{base}") {
+                (base, "{base}".len())
+            } else {
+                ("{", 1)
+            };
+            out.push_str(value);
+            rest = &rest[len..];
+        }
+        out.push_str(rest);
+
+        out
+    }
+}
+
+impl Default for TitleFormat {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// What a page's title is made of, in the order it was rendered.
+#[derive(Debug, Default, Serialize)]
+pub struct TitleParts {
+    /// From `{% title %}`.
+    pub title: Option<KString>,
+    /// From `{% title_segment %}`, outermost first.
+    pub segments: Vec<KString>,
+    pub base: Option<KString>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies() {
+        let parts = |title: Option<&str>, segments: &[&str], base: Option<&str>| TitleParts {
+            title: title.map(KString::from_ref),
+            segments: segments.iter().copied().map(KString::from_ref).collect(),
+            base: base.map(KString::from_ref),
+        };
+        let default = TitleFormat::default();
+        let custom = TitleFormat {
+            format: "{base} | {title}".into(),
+            separator: " / ".into(),
+        };
+
+        let title = default.apply(&parts(Some("Edit"), &["Account", "Settings"], Some("Site")));
+        assert_eq!(title.as_deref(), Some("Edit • Settings • Account • Site"));
+
+        let title = custom.apply(&parts(Some("Edit"), &["Account"], Some("Site")));
+        assert_eq!(title.as_deref(), Some("Site | Edit / Account"));
+
+        let title = custom.apply(&parts(None, &["Account"], None));
+        assert_eq!(title.as_deref(), Some("Account"));
+
+        assert_eq!(
+            default.apply(&parts(None, &[], Some("Site"))).as_deref(),
+            Some("Site")
+        );
+        assert_eq!(default.apply(&parts(None, &[], None)), None);
+    }
+
+    #[test]
+    fn substitutes_once() {
+        let format = TitleFormat {
+            format: "{{title}} - {base}".into(),
+            separator: " / ".into(),
+        };
+        assert_eq!(format.substitute("{base}", "{title}"), "{{base}} - {title}");
+
+        let no_title = TitleFormat {
+            format: "{base}".into(),
+            separator: " / ".into(),
+        };
+        assert_eq!(no_title.substitute("Edit", "Site"), "Edit • Site");
+    }
+}